@@ -0,0 +1,12 @@
+use ink::primitives::{AccountId, Hash};
+
+/// Shared interface of the external arbiter registries the escrow draws the arbiter
+/// of each dispute from, so no single arbiter is fixed for the deployment
+#[ink::trait_definition]
+pub trait ArbiterRegistry {
+    /// Draw the arbiter of a dispute, `seed` is unique to the dispute so the
+    /// registry may pick a random or rotating arbiter. `None` when no arbiter is
+    /// available.
+    #[ink(message)]
+    fn draw_arbiter(&mut self, seed: Hash) -> Option<AccountId>;
+}
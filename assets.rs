@@ -1,5 +1,10 @@
 use sp_runtime::MultiAddress;
 use ink::env::DefaultEnvironment;
+use scale::{Encode, Output};
+
+use crate::balances::BalancesCall;
+use crate::utility::UtilityCall;
+use crate::xcm::XcmCall;
 
 type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
 type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
@@ -9,6 +14,139 @@ pub enum RuntimeCall {
     /// Dispatches a call to the `Assets` pallet.
     #[codec(index = 50)]
     Assets(AssetsCall),
+    /// Dispatches a call to the `Balances` pallet.
+    #[codec(index = 10)]
+    Balances(BalancesCall),
+    /// Dispatches a call to the `PolkadotXcm` pallet.
+    #[codec(index = 31)]
+    PolkadotXcm(XcmCall),
+    /// Dispatches a call to the `Utility` pallet.
+    #[codec(index = 40)]
+    Utility(UtilityCall),
+    /// Dispatches a call to the `Nfts` pallet.
+    #[codec(index = 52)]
+    Nfts(NftsCall),
+}
+
+impl RuntimeCall {
+    /// Encode the call with the pallet and call indices of the runtime the escrow is
+    /// deployed on, nested calls included.
+    pub fn encode_indexed<T: Output + ?Sized>(&self, indices: &PalletIndices, dest: &mut T) {
+        let calls = &indices.calls;
+        match self {
+            Self::Assets(call) => {
+                dest.push_byte(indices.assets);
+                let index = match call {
+                    AssetsCall::Transfer { .. } => calls.assets_transfer,
+                    AssetsCall::TransferKeepAlive { .. } => calls.assets_transfer_keep_alive,
+                    AssetsCall::ApproveTransfer { .. } => calls.assets_approve_transfer,
+                    AssetsCall::TransferApproved { .. } => calls.assets_transfer_approved,
+                    AssetsCall::TransferAll { .. } => calls.assets_transfer_all,
+                };
+                encode_with_index(call, index, dest);
+            }
+            Self::Balances(call) => {
+                dest.push_byte(indices.balances);
+                encode_with_index(call, calls.balances_transfer, dest);
+            }
+            Self::PolkadotXcm(call) => {
+                dest.push_byte(indices.polkadot_xcm);
+                encode_with_index(call, calls.xcm_transfer_assets, dest);
+            }
+            Self::Utility(call) => {
+                dest.push_byte(indices.utility);
+                call.encode_indexed(indices, dest);
+            }
+            Self::Nfts(call) => {
+                dest.push_byte(indices.nfts);
+                encode_with_index(call, calls.nfts_transfer, dest);
+            }
+        }
+    }
+}
+
+/// Encode a pallet call with another call index than the one it is declared with,
+/// the arguments are encoded as declared
+fn encode_with_index<C: Encode, T: Output + ?Sized>(call: &C, index: u8, dest: &mut T) {
+    let encoded = call.encode();
+    dest.push_byte(index);
+    dest.write(&encoded[1..]);
+}
+
+/// Pallet indices of the runtime the escrow is deployed on, so one contract
+/// artifact works on runtimes ordering their pallets differently. The default is
+/// the layout `RuntimeCall` is declared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct PalletIndices {
+    pub assets: u8,
+    pub balances: u8,
+    pub polkadot_xcm: u8,
+    pub utility: u8,
+    pub nfts: u8,
+    /// Indices of the calls within their pallets
+    pub calls: CallIndices,
+}
+
+impl Default for PalletIndices {
+    fn default() -> Self {
+        Self {
+            assets: 50,
+            balances: 10,
+            polkadot_xcm: 31,
+            utility: 40,
+            nfts: 52,
+            calls: CallIndices::default(),
+        }
+    }
+}
+
+/// Call indices of the pallet calls the escrow dispatches, for runtimes running a
+/// pallet version that orders its calls differently. The default is the index each
+/// call is declared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct CallIndices {
+    pub assets_transfer: u8,
+    pub assets_transfer_keep_alive: u8,
+    pub assets_approve_transfer: u8,
+    pub assets_transfer_approved: u8,
+    pub assets_transfer_all: u8,
+    pub balances_transfer: u8,
+    pub xcm_transfer_assets: u8,
+    pub utility_batch_all: u8,
+    pub nfts_transfer: u8,
+}
+
+impl Default for CallIndices {
+    fn default() -> Self {
+        Self {
+            assets_transfer: 8,
+            assets_transfer_keep_alive: 9,
+            assets_approve_transfer: 22,
+            assets_transfer_approved: 25,
+            assets_transfer_all: 32,
+            balances_transfer: 0,
+            xcm_transfer_assets: 11,
+            utility_batch_all: 2,
+            nfts_transfer: 6,
+        }
+    }
+}
+
+/// Runtime call encoded with the pallet indices of the target runtime, what the
+/// escrow hands to `call_runtime`
+pub struct IndexedCall<'a> {
+    pub call: &'a RuntimeCall,
+    pub indices: PalletIndices,
+}
+
+impl Encode for IndexedCall<'_> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.call.encode_indexed(&self.indices, dest);
+    }
 }
 
 /// Defines relevant `Assets` pallet calls for web3 lottery.
@@ -25,5 +163,67 @@ pub enum AssetsCall {
         #[codec(compact)]
         amount: Balance,
     },
+    /// Move some assets from the sender account to another, keeping the sender
+    /// account alive.
+    ///
+    /// Used on releases when the owner keeps the escrow asset account above the
+    /// minimum balance.
+    #[codec(index = 9)]
+    TransferKeepAlive {
+        #[codec(compact)]
+        id: u128,
+        target: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        amount: Balance,
+    },
+    /// Approve an amount of asset for transfer by a delegated third-party account.
+    ///
+    /// Used to let another escrow pull a migrated position.
+    #[codec(index = 22)]
+    ApproveTransfer {
+        #[codec(compact)]
+        id: u128,
+        delegate: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        amount: Balance,
+    },
+    /// Transfer some asset balance from a previously delegated account to some
+    /// third-party account.
+    ///
+    /// Used to pull tokens the owner has approved through `approve_transfer`.
+    #[codec(index = 25)]
+    TransferApproved {
+        #[codec(compact)]
+        id: u128,
+        owner: MultiAddress<AccountId, ()>,
+        destination: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        amount: Balance,
+    },
+    /// Transfer the entire transferable balance from the sender asset account.
+    ///
+    /// Used to sweep the escrow asset account to the beneficiary on termination, and
+    /// on the payout emptying it under `TransferMethod::TransferAll`.
+    #[codec(index = 32)]
+    TransferAll {
+        #[codec(compact)]
+        id: u128,
+        dest: MultiAddress<AccountId, ()>,
+        keep_alive: bool,
+    },
 }
 
+
+/// Defines relevant `Nfts` pallet calls for NFT escrows.
+#[ink::scale_derive(Encode)]
+pub enum NftsCall {
+    /// Move an item from the sender account to another.
+    ///
+    /// Used to deliver an escrowed item to its recipient.
+    #[codec(index = 6)]
+    Transfer {
+        collection: u32,
+        item: u32,
+        dest: MultiAddress<AccountId, ()>,
+    },
+}
@@ -0,0 +1,20 @@
+use sp_runtime::MultiAddress;
+use ink::env::DefaultEnvironment;
+
+type AccountId = <DefaultEnvironment as ink::env::Environment>::AccountId;
+type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
+
+/// Defines relevant `Balances` pallet calls for the native escrow mode.
+#[ink::scale_derive(Encode)]
+pub enum BalancesCall {
+    /// Transfer some liquid free balance to another account, the sender may be
+    /// reaped (`transfer_allow_death`, formerly `transfer`).
+    ///
+    /// Used to batch native transfers in settlement runs.
+    #[codec(index = 0)]
+    Transfer {
+        dest: MultiAddress<AccountId, ()>,
+        #[codec(compact)]
+        value: Balance,
+    },
+}
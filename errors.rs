@@ -3,27 +3,195 @@ use scale::{Decode, Encode};
 use ink::env::Error as EnvError;
 
 /// Error Messages
+///
+/// The discriminants are stable error codes, variants must never be renumbered
+/// or reused, new variants take the next free number.
 #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum Error {
-    /// Bad origin error, e.g., wrong caller
-    BadOrigin,
-    /// Escrow is close
-    EscrowIsClose,
-    /// Escrow account not found
-    EscrowAccountNotFound,
-    /// One escrow per account only
-    EscrowAccountDuplicate,
-    /// Maximum escrow account has been reached
-    EscrowAccountMax,
+    /// Bad origin error, e.g., wrong caller (owner, manager or account owner only
+    /// messages)
+    BadOrigin = 0,
+    /// Escrow is close (`add`, `add_auto`, `fund`, top-ups and releases, per
+    /// `close_policy`)
+    EscrowIsClose = 1,
+    /// Escrow account not found (releases, refunds, tags, notes, funding, top-ups,
+    /// freezes)
+    EscrowAccountNotFound = 2,
+    /// One escrow per account only (`add`, `add_auto`)
+    EscrowAccountDuplicate = 3,
+    /// Maximum escrow account has been reached, capacity (`add`, `add_auto`)
+    EscrowAccountMax = 4,
+    /// Escrow is open (`withdraw_bond`, `refund_when_closed`)
+    EscrowIsOpen = 5,
+    /// Escrow still has accounts (`withdraw_bond`), or NFT escrows (`terminate`)
+    EscrowIsNotEmpty = 6,
+    /// Manager bond is below the required amount, under-collateralized (`open`,
+    /// `slash_bond`)
+    EscrowBondInsufficient = 7,
+    /// Escrow has not been close long enough for a refund (`refund_when_closed`)
+    EscrowRefundLocked = 8,
+    /// Recipient has not confirmed its address (`release`)
+    EscrowRecipientNotConfirmed = 9,
+    /// Tag is empty or too long (`add_tag`)
+    EscrowTagInvalid = 10,
+    /// Maximum tags per account has been reached (`add_tag`)
+    EscrowTagMax = 11,
+    /// Payout template not found (`add`, `release`)
+    EscrowTemplateNotFound = 12,
+    /// Payout template shares are empty, too many or do not add up to 10,000 bps
+    /// (`set_template`)
+    EscrowTemplateInvalid = 13,
+    /// Maximum contributors per account has been reached (`fund`)
+    EscrowContributorMax = 14,
+    /// The escrow asset cannot be rescued (`rescue_asset`)
+    EscrowAssetProtected = 15,
+    /// Native balance is below the requested amount (`withdraw_native`)
+    EscrowNativeInsufficient = 16,
+    /// Queued transfer failed and was moved to the tail of the queue (`flush`), the
+    /// transfer of a batch entry failed (`force_release_batch`), or the dispute bond
+    /// could not be pulled (`raise_dispute`)
+    EscrowTransferFailed = 17,
+    /// Releases are paused after an insolvency was detected (releases)
+    EscrowInsolvencyPaused = 18,
+    /// Add does not match the intent registered by the depositor (`add`)
+    EscrowIntentMismatch = 19,
+    /// Deposit intent not found (`cancel_intent`)
+    EscrowIntentNotFound = 20,
+    /// Deposit intent is past its time to live (`add`)
+    EscrowIntentExpired = 21,
+    /// Note is empty or too long (`add_note`)
+    EscrowNoteInvalid = 22,
+    /// Maximum notes per account has been reached (`add_note`)
+    EscrowNoteMax = 23,
+    /// Escrow account is frozen (releases)
+    EscrowAccountFrozen = 24,
+    /// Split is above 10,000 bps (`force_release_split`)
+    EscrowSplitInvalid = 25,
+    /// Escrow account release is paused by its depositor (releases)
+    EscrowAccountPaused = 26,
+    /// Document hash already attests another release (`release_with_attestation`)
+    EscrowAttestationUsed = 27,
+    /// Recipient is not on this chain (`transfer_to_escrow`)
+    EscrowRecipientUnsupported = 28,
+    /// Feature group is disabled for this deployment (`force_release`, disputes)
+    EscrowFeatureDisabled = 29,
+    /// No registry is set (`heartbeat`)
+    EscrowRegistryNotSet = 30,
+    /// Registry rejected the escrow (`new_with_registry`, `set_registry`, `heartbeat`)
+    EscrowRegistryRejected = 31,
+    /// Amount is zero or above the account balance (`release_partial`,
+    /// `force_release_partial`, `fund`)
+    EscrowAmountInvalid = 32,
+    /// No pending release with this id
+    EscrowPendingReleaseNotFound = 33,
+    /// Pending release was not countersigned within the dual control window
+    EscrowPendingReleaseExpired = 34,
+    /// Escrow account lock period has not passed
+    EscrowStillLocked = 35,
+    /// Escrow account has no expiry or has not expired yet
+    EscrowNotExpired = 36,
+    /// Spender has no allowance on the escrow account or the amount is above it
+    EscrowAllowanceExceeded = 37,
+    /// Escrow asset is not held in the backend the message requires
+    EscrowWrongBackend = 38,
+    /// Quorum threshold is above the number of quorum managers
+    EscrowQuorumInvalid = 39,
+    /// Quorum manager already approved the pending release
+    EscrowAlreadyApproved = 40,
+    /// Escrow account is disputed, only the arbiter can settle it
+    EscrowAccountDisputed = 41,
+    /// Dispute window of the escrow account has passed
+    EscrowDisputeWindowClosed = 42,
+    /// No dispute is pending on the escrow account
+    EscrowDisputeNotFound = 43,
+    /// Protocol fee is above 10,000 bps (`set_fee`)
+    EscrowFeeInvalid = 44,
+    /// No upgrade to this code hash was proposed (`upgrade`, `cancel_upgrade`)
+    EscrowUpgradeNotProposed = 45,
+    /// Upgrade timelock has not passed yet, or the delay would be shortened
+    EscrowUpgradeLocked = 46,
+    /// Code hash could not be set, e.g. the code is not uploaded
+    EscrowUpgradeFailed = 47,
+    /// Stored layout is newer than the code (`migrate`)
+    EscrowVersionUnsupported = 48,
+    /// Queued transfers must be settled first (`terminate`)
+    EscrowQueueNotEmpty = 49,
+    /// Operation is halted by its pause switch (`set_paused`)
+    EscrowOperationPaused = 50,
+    /// Depositor is not on the allow-list (adds, deposits, intents, positions
+    /// received)
+    EscrowDepositorNotAllowed = 51,
+    /// Recipient is blocked by the owner (adds, recipient changes, releases)
+    RecipientBlocked = 52,
+    /// Release signature nonce is not the next nonce of the account
+    EscrowNonceInvalid = 53,
+    /// Release signature is malformed or not signed by the account
+    EscrowSignatureInvalid = 54,
+    /// Vesting schedule has a zero duration or a cliff past its end
+    EscrowVestingInvalid = 55,
+    /// Nothing vested yet, or the amount exceeds the vested part
+    EscrowNotVested = 56,
+    /// Mutual-consent release not approved by both the depositor and the recipient
+    EscrowReleaseNotApproved = 57,
+    /// Escrow account waits for its recipient to accept it
+    EscrowAccountPending = 58,
+    /// Cancel window of the pending escrow account has passed
+    EscrowCancelWindowClosed = 59,
+    /// Escrow holds less of the asset than the release and the queued transfers
+    /// need (balance checks)
+    InsufficientEscrowFunds = 60,
+    /// Escrow asset balance cannot be read while balance checks are off
+    EscrowBalanceUnavailable = 61,
+    /// Escrow account has no failed release to convert into a refund
+    EscrowReleaseNotFailed = 62,
+    /// Deposit intent queue is full until expired intents are purged
+    EscrowIntentMax = 63,
+    /// Arbiter panel is not 3 or 5 distinct accounts other than the manager
+    EscrowPanelInvalid = 64,
+    /// Arbiter already ruled on the dispute
+    EscrowAlreadyRuled = 65,
+    /// Arbiter registry has no arbiter to draw for the dispute
+    EscrowArbiterUnavailable = 66,
 }  
 
 /// Runtime call execution error
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
 pub enum RuntimeError {
     /// Failed to dispatch a runtime call.
-    CallRuntimeFailed,
+    CallRuntimeFailed = 0,
+    /// Failed to transfer native balance.
+    NativeTransferFailed = 1,
+    /// PSP22 token call failed, or the token cannot be moved this way.
+    TokenTransferFailed = 2,
+    /// Asset transfer failed, the sender holds less than the amount.
+    BalanceLow = 3,
+    /// Asset transfer failed, the sender has no account of the asset.
+    NoAccount = 4,
+    /// Asset transfer failed, the asset or one of the accounts is frozen.
+    AssetFrozen = 5,
+    /// Asset transfer failed, the amount would leave the receiver below the
+    /// minimum balance.
+    BelowMinimum = 6,
+    /// Asset transfer failed, the asset does not exist.
+    UnknownAsset = 7,
+}
+
+impl RuntimeError {
+    /// Failure reason of an asset transfer from the code of the assets chain
+    /// extension `transfer_check`, `call_runtime` itself only reports that the call
+    /// failed. Codes without a specific reason stay `CallRuntimeFailed`.
+    pub fn from_transfer_check(code: u8) -> Self {
+        match code {
+            1 => Self::BalanceLow,
+            2 => Self::NoAccount,
+            3 => Self::AssetFrozen,
+            4 => Self::BelowMinimum,
+            5 => Self::UnknownAsset,
+            _ => Self::CallRuntimeFailed,
+        }
+    }
 }
 
 /// Unified contract error type.
@@ -36,6 +204,34 @@ pub enum ContractError {
     Runtime(RuntimeError),
 }
 
+/// Offset of runtime error codes in the contract error code space
+pub const RUNTIME_ERROR_CODE_OFFSET: u16 = 256;
+
+impl Error {
+    /// Stable numeric code of the error
+    pub fn error_code(&self) -> u16 {
+        self.clone() as u16
+    }
+}
+
+impl RuntimeError {
+    /// Stable numeric code of the runtime error
+    pub fn error_code(&self) -> u16 {
+        RUNTIME_ERROR_CODE_OFFSET + self.clone() as u16
+    }
+}
+
+impl ContractError {
+    /// Stable numeric code of the contract error, internal errors use codes below
+    /// `RUNTIME_ERROR_CODE_OFFSET` and runtime errors codes from it
+    pub fn error_code(&self) -> u16 {
+        match self {
+            Self::Internal(err) => err.error_code(),
+            Self::Runtime(err) => err.error_code(),
+        }
+    }
+}
+
 // Error conversions for convenience.
 impl From<Error> for ContractError {
     fn from(err: Error) -> Self {
@@ -0,0 +1,63 @@
+use ink::env::{DefaultEnvironment, Environment};
+
+type AccountId = <DefaultEnvironment as Environment>::AccountId;
+type Balance = <DefaultEnvironment as Environment>::Balance;
+
+/// Read-only `pallet_assets` queries the runtime exposes to the escrow, which
+/// `call_runtime` cannot do as it only dispatches calls.
+///
+/// The runtime registers the extension under id `0x4153`, answers function `1` with
+/// the SCALE encoded `Assets::balance(asset_id, who)` and function `2` with the
+/// outcome of a transfer, so transfers that would fail report their reason before
+/// they are dispatched. Both are runtime code the escrow relies on, not part of
+/// `pallet_contracts`; see the README for the codes the runtime must return.
+#[ink::chain_extension(extension = 0x4153)]
+pub trait AssetsExtension {
+    type ErrorCode = AssetsExtensionError;
+
+    /// Balance of `who` in asset `asset_id`, zero without an asset account
+    #[ink(function = 1, handle_status = false)]
+    fn balance_of(asset_id: u128, who: AccountId) -> Balance;
+
+    /// Outcome of transferring `amount` of asset `asset_id` from `from` to `to`
+    /// without dispatching it, from `can_withdraw` on `from` and `can_deposit` on
+    /// `to`: 0 the transfer succeeds, 1 balance low, 2 no account, 3 frozen, 4 below
+    /// minimum, 5 unknown asset, anything else an other failure
+    #[ink(function = 2, handle_status = false)]
+    fn transfer_check(asset_id: u128, from: AccountId, to: AccountId, amount: Balance) -> u8;
+}
+
+/// Status codes of the assets chain extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum AssetsExtensionError {
+    /// Query failed in the runtime
+    Failed,
+}
+
+impl ink::env::chain_extension::FromStatusCode for AssetsExtensionError {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            _ => Err(Self::Failed),
+        }
+    }
+}
+
+/// Escrow contract environment, the default environment with the assets chain
+/// extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[ink::scale_derive(TypeInfo)]
+pub enum EscrowEnvironment {}
+
+impl Environment for EscrowEnvironment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = AccountId;
+    type Balance = Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+
+    type ChainExtension = AssetsExtension;
+}
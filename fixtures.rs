@@ -0,0 +1,151 @@
+use ink::prelude::vec::Vec;
+use ink::primitives::{AccountId, Hash};
+
+use crate::escrow::{Escrow, FEATURE_ALL};
+
+/// Escrow account set up by a fixture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureAccount {
+    pub reference: u16,
+    pub account: AccountId,
+    pub amount: u128,
+    pub recipient: AccountId,
+}
+
+/// `Assets` pallet calls funding the escrow asset for an e2e run, dispatched by the
+/// suite through its own client (e.g. `runtime_call(.., "Assets", "create", ..)`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetSetup {
+    pub asset_id: u128,
+    /// `Assets::create` admin
+    pub admin: AccountId,
+    /// `Assets::create` minimum balance
+    pub min_balance: u128,
+    /// `Assets::mint` beneficiaries and amounts
+    pub mints: Vec<(AccountId, u128)>,
+}
+
+/// Deterministic escrow states for integration tests, the same fixture always
+/// builds the same references, accounts, amounts and recipients. Unit tests call
+/// `build`, e2e suites instantiate `EscrowRef::new_with_features` with the fixture
+/// configuration, fund the asset per `asset_setup` and add `accounts` through `add`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscrowFixture {
+    pub asset_id: u128,
+    pub maximum_accounts: u16,
+    pub features: u32,
+    /// Amount of the first account, account `n` holds `(n + 1) * base_amount`
+    pub base_amount: u128,
+    pub accounts: Vec<FixtureAccount>,
+}
+
+impl EscrowFixture {
+    /// Fixture of an escrow of asset 1 without accounts
+    pub fn new() -> Self {
+        Self {
+            asset_id: 1,
+            maximum_accounts: 100,
+            features: FEATURE_ALL,
+            base_amount: 1_000,
+            accounts: Vec::new(),
+        }
+    }
+
+    /// Fixture with `n` escrow accounts, references `0..n`
+    pub fn with_accounts(n: u16) -> Self {
+        let mut fixture = Self::new();
+        fixture.maximum_accounts = fixture.maximum_accounts.max(n);
+        fixture.accounts = (0..n).map(|i| fixture.account(i)).collect();
+        fixture
+    }
+
+    /// Set the escrow asset
+    pub fn asset_id(mut self, asset_id: u128) -> Self {
+        self.asset_id = asset_id;
+        self
+    }
+
+    /// Set the maximum accounts
+    pub fn maximum_accounts(mut self, maximum_accounts: u16) -> Self {
+        self.maximum_accounts = maximum_accounts;
+        self
+    }
+
+    /// Set the feature groups (`FEATURE_*` bitflags)
+    pub fn features(mut self, features: u32) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Set the amount of the first account, the accounts are recomputed
+    pub fn base_amount(mut self, base_amount: u128) -> Self {
+        self.base_amount = base_amount;
+        let n = self.accounts.len() as u16;
+        self.accounts = (0..n).map(|i| self.account(i)).collect();
+        self
+    }
+
+    /// Deterministic depositor of account `n`
+    pub fn depositor(n: u16) -> AccountId {
+        Self::fixture_id(n, 0xD0)
+    }
+
+    /// Deterministic recipient of account `n`
+    pub fn recipient(n: u16) -> AccountId {
+        Self::fixture_id(n, 0xE0)
+    }
+
+    /// Sum of the fixture account amounts
+    pub fn total_amount(&self) -> u128 {
+        self.accounts
+            .iter()
+            .fold(0u128, |total, a| total.saturating_add(a.amount))
+    }
+
+    /// Build the escrow in the off-chain test environment, the caller becomes owner
+    /// and manager and adds the fixture accounts
+    pub fn build(&self) -> Escrow {
+        let mut escrow = Escrow::new_with_features(self.asset_id, self.maximum_accounts, self.features);
+        for a in self.accounts.iter() {
+            escrow
+                .add_escrow_account(a.reference, a.account, self.asset_id, a.amount, a.recipient, Hash::default(), None, None, None)
+                .expect("fixture account add failed");
+        }
+        escrow
+    }
+
+    /// `Assets` pallet setup for an e2e run: create the escrow asset and mint the
+    /// fixture amounts to the escrow contract, which holds the escrowed funds
+    pub fn asset_setup(&self, admin: AccountId, escrow: AccountId) -> AssetSetup {
+        AssetSetup {
+            asset_id: self.asset_id,
+            admin,
+            min_balance: 1,
+            mints: ink::prelude::vec![(escrow, self.total_amount())],
+        }
+    }
+
+    /// Fixture account `n`
+    fn account(&self, n: u16) -> FixtureAccount {
+        FixtureAccount {
+            reference: n,
+            account: Self::depositor(n),
+            amount: self.base_amount.saturating_mul(n as u128 + 1),
+            recipient: Self::recipient(n),
+        }
+    }
+
+    /// Account id made of `n` and a role byte, distinct from the default test
+    /// accounts
+    fn fixture_id(n: u16, role: u8) -> AccountId {
+        let mut bytes = [role; 32];
+        bytes[..2].copy_from_slice(&n.to_le_bytes());
+        AccountId::from(bytes)
+    }
+}
+
+impl Default for EscrowFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
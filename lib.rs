@@ -1,22 +1,72 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+/// Arbiter registry interface for drawing dispute arbiters
+pub mod arbiters;
+
 /// pallet_assets runtime calls
 pub mod assets;
 
+/// pallet_balances runtime calls for the native escrow mode
+pub mod balances;
+
 /// Errors
 pub mod errors;
 
-#[ink::contract]
-mod escrow {
+/// pallet_assets balance reads through a chain extension
+pub mod extension;
+
+/// PSP22 token interface for the PSP22 transfer backend
+pub mod psp22;
+
+/// PSP34 escrow position NFTs
+pub mod psp34;
+
+/// Escrow-to-escrow position transfers
+pub mod receiver;
+
+/// Companion registry interface for escrow discovery
+pub mod registry;
+
+/// Access control roles
+pub mod roles;
+
+/// pallet_utility runtime calls for settlement runs
+pub mod utility;
+
+/// pallet_xcm runtime calls for remote payouts
+pub mod xcm;
+
+/// Deterministic escrow states for downstream integration tests
+#[cfg(feature = "test-fixtures")]
+pub mod fixtures;
+
+#[ink::contract(env = crate::extension::EscrowEnvironment)]
+pub mod escrow {
 
     use ink::prelude::vec::Vec;
+    use ink::prelude::string::String;
+    use ink::storage::Mapping;
 
+    use crate::arbiters::ArbiterRegistry;
     use crate::errors::{Error, RuntimeError, ContractError};
-    use crate::assets::{AssetsCall, RuntimeCall};
+    use crate::assets::{AssetsCall, IndexedCall, NftsCall, PalletIndices, RuntimeCall};
+    use crate::balances::BalancesCall;
+    use crate::psp22::PSP22;
+    use crate::psp34::{Id, PSP34, PSP34Error, PSP34Metadata};
+    use crate::receiver::EscrowReceiver;
+    use crate::registry::EscrowRegistry;
+    use crate::roles::Role;
+    use crate::utility::UtilityCall;
+    use crate::xcm::{self, WeightLimit, XcmCall};
+    use ink::env::hash::Blake2x256;
 
     /// Success Messages
+    ///
+    /// Operations on an escrow account, a template or an amount carry the identifiers
+    /// involved, so the `EscrowEvent` can be traced without a follow-up query.
     #[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
-    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    #[allow(clippy::enum_variant_names)]
     pub enum Success {
         /// Escrow setup successful
         EscrowSetupSuccess,
@@ -25,9 +75,148 @@ mod escrow {
         /// Escrow open successful
         EscrowOpenSuccess,
         /// Escrow account added
-        EscrowAccountAdded,
+        EscrowAccountAdded { reference: u16 },
         /// Escrow account released
-        EscrowAccountReleased,
+        EscrowAccountReleased { reference: u16, amount: u128, fee: u128 },
+        /// Part of an escrow account released, the account stays with the remainder
+        EscrowAccountPartiallyReleased { reference: u16, amount: u128, fee: u128, remaining: u128 },
+        /// Escrow account refunded to the depositor
+        EscrowAccountRefunded { reference: u16, amount: u128 },
+        /// Escrow account funded by a third party
+        EscrowAccountFunded { reference: u16, amount: u128 },
+        /// Escrow account topped up by the manager
+        EscrowAccountToppedUp { reference: u16, amount: u128 },
+        /// Escrow account frozen
+        EscrowAccountFrozen { reference: u16 },
+        /// Escrow account unfrozen
+        EscrowAccountUnfrozen { reference: u16 },
+        /// Escrow account paused by its depositor
+        EscrowAccountPaused { reference: u16, until: BlockNumber },
+        /// Escrow account expiry set
+        EscrowExpirySet { reference: u16, expires_at: BlockNumber },
+        /// Spender allowed to release part of an escrow account up to a cap
+        EscrowSpenderApproved { reference: u16, spender: AccountId, cap: u128 },
+        /// Spender allowance on an escrow account revoked
+        EscrowSpenderRevoked { reference: u16, spender: AccountId },
+        /// NFT escrow added
+        EscrowNftAdded { reference: u16, collection: u32, item: u32 },
+        /// NFT escrow released to its recipient
+        EscrowNftReleased { reference: u16, collection: u32, item: u32 },
+        /// Role granted to an account
+        EscrowRoleGranted { role: Role, account: AccountId },
+        /// Role revoked from an account
+        EscrowRoleRevoked { role: Role, account: AccountId },
+        /// Escrow account converted to a refund toward the depositor
+        EscrowAccountConverted { reference: u16 },
+        /// Escrow account transferred to another escrow
+        EscrowAccountTransferred { reference: u16, amount: u128 },
+        /// Escrow account received from another escrow
+        EscrowAccountReceived { reference: u16, amount: u128 },
+        /// Mistaken asset deposit rescued
+        EscrowAssetRescued { asset_id: u128, amount: u128 },
+        /// Native balance withdrawn
+        EscrowNativeWithdrawn { amount: Balance },
+        /// Dust pot withdrawn
+        EscrowDustWithdrawn { amount: u128 },
+        /// Native balance funded for storage growth
+        EscrowStorageFunded { amount: Balance },
+        /// Deferred transfer queue flushed
+        EscrowQueueFlushed { processed: u32 },
+        /// State checkpoint emitted
+        EscrowCheckpointEmitted { checkpoint: u32 },
+        /// Escrow registered with or reported to the registry
+        EscrowRegistryUpdated,
+        /// Escrow solvent
+        EscrowSolvent,
+        /// Releases resumed after an insolvency
+        EscrowResumed,
+        /// Correlation id of an escrow account set
+        EscrowCorrelationSet { reference: u16 },
+        /// Deposit intent registered
+        EscrowIntentRegistered { reference: u16 },
+        /// Deposit intent cancelled
+        EscrowIntentCancelled,
+        /// Expired deposit intents purged
+        EscrowIntentsPurged { purged: u32 },
+        /// Escrow account recipient confirmed
+        EscrowRecipientConfirmed { reference: u16 },
+        /// Escrow account recipient changed from `old` to `new`
+        EscrowRecipientSet { reference: u16, old: Recipient, new: Recipient },
+        /// Payout address of a recipient set
+        EscrowPayoutAddressSet,
+        /// Escrow configuration updated
+        EscrowConfigUpdated,
+        /// Default terms of the accounts added from now on updated
+        EscrowTermsUpdated { lock_period: BlockNumber, dispute_window: BlockNumber, cancel_window: BlockNumber },
+        /// New owner nominated, waiting for its acceptance
+        EscrowOwnerNominated { owner: AccountId },
+        /// Ownership accepted by the nominated owner
+        EscrowOwnerChanged { owner: AccountId },
+        /// New manager nominated, waiting for its acceptance
+        EscrowManagerNominated { manager: AccountId },
+        /// Manager role accepted by the nominated manager
+        EscrowManagerChanged { manager: AccountId },
+        /// Manager override release waiting for the owner countersignature
+        EscrowReleaseProposed { id: u32 },
+        /// Pending manager override release cancelled
+        EscrowReleaseCancelled { id: u32 },
+        /// Pending manager override release approved by a quorum manager, short of
+        /// the threshold
+        EscrowReleaseApproved { id: u32, approvals: u8 },
+        /// Matured escrow accounts released by a sweep
+        EscrowMaturedSwept { released: u32, amount: u128 },
+        /// Dispute raised on an escrow account, which is frozen until resolved
+        EscrowDisputeRaised { reference: u16 },
+        /// Dispute resolved by the arbiter, the escrow account is settled
+        EscrowDisputeResolved { reference: u16, outcome: DisputeOutcome },
+        /// Ruling of a panel arbiter recorded, the dispute waits for the other
+        /// arbiters
+        EscrowDisputeRuled { reference: u16, rulings: u8 },
+        /// Arbiter drawn from the arbiter registry for a dispute
+        EscrowArbiterAssigned { reference: u16, arbiter: AccountId },
+        /// Code upgrade proposed, executable from the block `execute_at`
+        EscrowUpgradeProposed { code_hash: Hash, execute_at: BlockNumber },
+        /// Proposed code upgrade cancelled
+        EscrowUpgradeCancelled,
+        /// Contract code replaced
+        EscrowUpgraded { code_hash: Hash },
+        /// Storage migrated from the layout version `from` to `to`
+        EscrowMigrated { from: u32, to: u32 },
+        /// Escrow refunded its accounts and terminated, the storage deposit went to
+        /// the beneficiary
+        EscrowTerminated { beneficiary: AccountId },
+        /// Pause switches updated
+        EscrowPauseUpdated { flags: u8 },
+        /// Depositor added to or removed from the allow-list
+        EscrowAllowListUpdated { account: AccountId, allowed: bool },
+        /// Recipient blocked or unblocked by the owner
+        EscrowRecipientBlockUpdated { recipient: AccountId, blocked: bool },
+        /// Linear vesting schedule set on an escrow account
+        EscrowVestingSet { reference: u16 },
+        /// Mutual-consent release required or no longer required on an escrow account
+        EscrowMutualReleaseSet { reference: u16, enabled: bool },
+        /// Release of a mutual-consent escrow account approved by one of its parties
+        EscrowReleaseConsented { reference: u16, approver: AccountId },
+        /// Pending escrow account accepted by its recipient
+        EscrowAccountAccepted { reference: u16 },
+        /// Pending escrow account cancelled by its depositor, the balance refunded
+        EscrowAccountCancelled { reference: u16, amount: u128 },
+        /// Escrow account tag added
+        EscrowTagAdded { reference: u16 },
+        /// Escrow account tag removed
+        EscrowTagRemoved { reference: u16 },
+        /// Escrow account internal note added
+        EscrowNoteAdded { reference: u16 },
+        /// Payout template saved
+        EscrowTemplateSaved { id: u16 },
+        /// Payout template removed
+        EscrowTemplateRemoved { id: u16 },
+        /// Manager bond posted
+        EscrowBondPosted { amount: u128 },
+        /// Manager bond withdrawn
+        EscrowBondWithdrawn { amount: u128 },
+        /// Manager bond slashed
+        EscrowBondSlashed { amount: u128 },
     }      
 
     /// Escrow status
@@ -38,6 +227,16 @@ mod escrow {
         EmitError(Error),
     }       
 
+    impl EscrowStatus {
+        /// Stable numeric code of the emitted error, if any
+        pub fn error_code(&self) -> Option<u16> {
+            match self {
+                Self::EmitSuccess(_) => None,
+                Self::EmitError(err) => Some(err.error_code()),
+            }
+        }
+    }
+
     /// Escrow event
     #[ink(event)]
     pub struct EscrowEvent {
@@ -46,6 +245,271 @@ mod escrow {
         status: EscrowStatus,
     }  
 
+    /// Escrow account added by the manager, a deposit or a position received from
+    /// another escrow
+    #[ink(event)]
+    pub struct AccountAdded {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        recipient: Recipient,
+        #[ink(topic)]
+        reference: u16,
+        asset_id: u128,
+        amount: u128,
+    }
+
+    /// Transfer out of the escrow failed, with the reason when the runtime reports
+    /// one
+    #[ink(event)]
+    pub struct TransferFailed {
+        #[ink(topic)]
+        target: AccountId,
+        asset_id: u128,
+        amount: u128,
+        error: RuntimeError,
+    }
+
+    /// Escrow account released to its recipient, in full or in part
+    #[ink(event)]
+    pub struct Released {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        recipient: Recipient,
+        #[ink(topic)]
+        reference: u16,
+        amount: u128,
+    }
+
+    /// Escrow account released by a manager override, `recipient_amount` of
+    /// `amount` went to the recipient chosen by the manager and the rest to the
+    /// depositor
+    #[ink(event)]
+    pub struct ForceReleased {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        #[ink(topic)]
+        reference: u16,
+        operator: AccountId,
+        amount: u128,
+        recipient_amount: u128,
+    }
+
+    /// Escrow set up by the owner, the existing accounts were cleared
+    #[ink(event)]
+    pub struct Setup {
+        #[ink(topic)]
+        asset_id: u128,
+        #[ink(topic)]
+        manager: AccountId,
+        maximum_accounts: u16,
+        bond_required: u128,
+    }
+
+    /// Escrow closed
+    #[ink(event)]
+    pub struct Closed {
+        #[ink(topic)]
+        operator: AccountId,
+    }
+
+    /// Escrow opened
+    #[ink(event)]
+    pub struct Opened {
+        #[ink(topic)]
+        operator: AccountId,
+    }
+
+    /// Queued transfer of an escrow account executed by a settlement run, emitted per
+    /// account even when its transfer was summed with others to the same target
+    #[ink(event)]
+    pub struct TransferSettled {
+        #[ink(topic)]
+        reference: u16,
+        target: AccountId,
+        amount: u128,
+    }
+
+    /// PSP34 position NFT transfer, minted from `None` and burned to `None`
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    /// PSP34 position NFT approval
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        #[ink(topic)]
+        id: Option<Id>,
+        approved: bool,
+    }
+
+    /// Funding transaction committed when an escrow account is added
+    #[ink(event)]
+    pub struct FundingCommitted {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        reference: u16,
+        amount: u128,
+        /// Hash of the funding transaction verified by the manager
+        #[ink(topic)]
+        tx_hash: Hash,
+    }
+
+    /// Release attested with the hash of a delivery or acceptance document
+    #[ink(event)]
+    pub struct ReleaseAttested {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        reference: u16,
+        amount: u128,
+        /// Hash of the document bound to the release
+        #[ink(topic)]
+        doc_hash: Hash,
+    }
+
+    /// Account transfer below the asset minimum balance kept in the dust pot
+    #[ink(event)]
+    pub struct DustCollected {
+        #[ink(topic)]
+        reference: u16,
+        /// Intended target of the transfer
+        target: AccountId,
+        amount: u128,
+    }
+
+    /// Batch stopped before running out of gas, entries from `cursor` on were not
+    /// processed and can be sent again
+    #[ink(event)]
+    pub struct BatchPartiallyProcessed {
+        #[ink(topic)]
+        operator: AccountId,
+        /// Index of the first unprocessed entry
+        cursor: u32,
+        /// Number of entries in the batch
+        total: u32,
+    }
+
+    /// Insolvency detected, releases are paused until the owner resumes them
+    #[ink(event)]
+    pub struct InsolvencyDetected {
+        #[ink(topic)]
+        operator: AccountId,
+        /// Escrow asset owed (accounts, queued transfers and manager bond)
+        liabilities: u128,
+        /// Escrow asset held by the contract
+        balance: u128,
+    }
+
+    /// Reconciliation found the escrow asset balance short of the liabilities
+    #[ink(event)]
+    pub struct Insolvent {
+        #[ink(topic)]
+        operator: AccountId,
+        /// Liabilities not covered by the escrow asset balance
+        deficit: u128,
+    }
+
+    /// Reconciliation found the escrow asset balance covering the liabilities
+    #[ink(event)]
+    pub struct Solvent {
+        #[ink(topic)]
+        operator: AccountId,
+        /// Escrow asset balance above the liabilities
+        surplus: u128,
+    }
+
+    /// Snapshot of the escrow configuration and counters with the Merkle root of the
+    /// escrow accounts, so an indexer can bootstrap from the latest checkpoint
+    /// instead of replaying the full history
+    #[ink(event)]
+    pub struct StateCheckpoint {
+        /// Sequence number of the checkpoint
+        #[ink(topic)]
+        checkpoint: u32,
+        block_number: BlockNumber,
+        asset_id: u128,
+        owner: AccountId,
+        manager: AccountId,
+        maximum_accounts: u16,
+        status: u8,
+        close_policy: u8,
+        bond_required: u128,
+        bond: u128,
+        reference_counter: u16,
+        account_count: u16,
+        total_balance: u128,
+        queued_amount: u128,
+        dust_pot: u128,
+        /// Merkle root of the escrow accounts, see `accounts_root`
+        accounts_root: Hash,
+    }
+
+    /// Escrow account event, emitted next to the `EscrowEvent` of operations on an
+    /// account carrying a correlation id so a business transaction can be traced
+    /// across contracts through one topic filter
+    #[ink(event)]
+    pub struct AccountEvent {
+        #[ink(topic)]
+        correlation_id: Hash,
+        #[ink(topic)]
+        reference: u16,
+        status: EscrowStatus,
+    }
+
+    /// Timelock of an escrow account ends soon, so notification services can alert
+    /// the depositor and recipient without computing the schedule themselves
+    #[ink(event)]
+    pub struct ReleaseDueSoon {
+        #[ink(topic)]
+        reference: u16,
+        /// Block from which the account can be released
+        due_block: BlockNumber,
+    }
+
+    /// Escrow account recipient
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum Recipient {
+        /// Account on this chain
+        Local(AccountId),
+        /// EVM address, paid to its mapped account on this chain
+        Evm([u8; 20]),
+        /// Account on another chain, as SCALE-encoded versioned XCM locations of the
+        /// destination chain and of the beneficiary there
+        Remote { dest: Vec<u8>, beneficiary: Vec<u8> },
+    }
+
+    impl Recipient {
+        /// Account paid on this chain, EVM addresses use the fallback account mapping
+        /// (the address followed by twelve `0xEE` bytes)
+        pub fn local_account(&self) -> Option<AccountId> {
+            match self {
+                Self::Local(account) => Some(*account),
+                Self::Evm(address) => {
+                    let mut bytes = [0xEEu8; 32];
+                    bytes[..20].copy_from_slice(address);
+                    Some(AccountId::from(bytes))
+                }
+                Self::Remote { .. } => None,
+            }
+        }
+    }
+
     /// Escrow Account
     #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
@@ -54,339 +518,10650 @@ mod escrow {
         pub reference: u16,
         /// Account address
         pub account: AccountId,
+        /// Asset of the balance
+        pub asset_id: u128,
         /// Free balance
         pub balance: u128,
-        /// Recipient address
-        pub recipient: AccountId,
-        /// Status (0-Frozen, 1-Liquid)
+        /// Recipient, on this chain or another execution environment
+        pub recipient: Recipient,
+        /// Status (0-Frozen, 1-Liquid, 2-Disputed, 3-Pending acceptance)
         pub status: u8,
+        /// Recipient confirmed the ownership of its address
+        pub confirmed: bool,
+        /// Labels used to group accounts
+        pub tags: Vec<String>,
+        /// Payout template splitting the release among several recipients
+        pub template: Option<u16>,
+        /// Position is represented by a PSP34 NFT with the reference as token id
+        pub nft: bool,
+        /// Block number the account was added
+        pub added_at: BlockNumber,
+        /// Hash of the funding transaction verified by the manager
+        pub tx_hash: Hash,
+        /// Correlation id supplied by the dApp, a topic of the account events
+        pub correlation_id: Option<Hash>,
+        /// Block until which the depositor paused the release of the account
+        pub paused_until: BlockNumber,
+        /// Block before which the account cannot be released, 0 = no lock
+        pub release_after: BlockNumber,
+        /// Block from which anyone can send the balance back to the depositor,
+        /// 0 = never expires
+        pub expires_at: BlockNumber,
     }  
 
-    /// Escrow storage
-    #[ink(storage)]
-    pub struct Escrow {
-        /// Escrow asset
-        pub asset_id: u128,
-        /// Escrow owner
-        pub owner: AccountId,
-        /// Escrow manager
-        pub manager: AccountId,
-        /// Maximum accounts the escrow can handle
-        pub maximum_accounts: u16,
-        /// Escrow accounts
-        pub accounts: Vec<Account>,
-        /// Status (0-Open, 1-Close)
-        pub status: u8,
+    /// Escrow account activity
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum Activity {
+        /// Account added by the manager
+        Added,
+        /// Account released by the account owner
+        Released,
+        /// Account released by the manager
+        ForceReleased,
+        /// Account refunded to the depositor
+        Refunded,
+        /// Recipient confirmed the ownership of its address
+        RecipientConfirmed,
+        /// Position NFT transferred to a new depositor
+        Transferred,
+        /// Account funded by a third party
+        Funded,
+        /// Account transferred to another escrow
+        TransferredOut,
+        /// Account received from another escrow
+        TransferredIn,
+        /// Account balance topped up by the manager
+        ToppedUp,
+        /// Account frozen by the manager
+        Frozen,
+        /// Account unfrozen by the manager
+        Unfrozen,
+        /// Account release paused by the depositor
+        Paused,
+        /// Account converted to a refund toward the depositor
+        ConvertedToRefund,
+        /// Expired account sent back to the depositor
+        Reclaimed,
+        /// Account opened by the depositor pulling its own funds
+        Deposited,
+        /// Part of the account balance released
+        PartiallyReleased,
+        /// Dispute raised by the depositor or the recipient
+        Disputed,
+        /// Account settled by the arbiter resolving its dispute
+        DisputeResolved,
+        /// Recipient changed by the depositor or the manager
+        RecipientChanged,
+        /// Pending account accepted by the recipient
+        Accepted,
+        /// Pending account cancelled by the depositor
+        Cancelled,
     }
 
+    /// Escrow account history entry
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct History {
+        /// Activity done on the account
+        pub activity: Activity,
+        /// Block number of the activity
+        pub block_number: BlockNumber,
+    }
 
-    impl Escrow {
+    /// Maximum history entries kept per reference, older entries are dropped
+    pub const MAXIMUM_HISTORY: usize = 16;
 
-        /// Create new escrow service
-        #[ink(constructor)]
-        pub fn new(asset_id: u128, 
-            maximum_accounts: u16) -> Self {
+    /// Default blocks the escrow must stay close before depositors can refund (~7 days)
+    pub const DEFAULT_REFUND_DELAY: BlockNumber = 100_800;
 
-            let caller: ink::primitives::AccountId = Self::env().caller();
+    /// Default blocks after an add during which a dispute may be raised (~3 days)
+    pub const DEFAULT_DISPUTE_WINDOW: BlockNumber = 43_200;
 
-            Self { 
-                asset_id: asset_id, 
-                owner: caller,
-                manager: caller,
-                maximum_accounts: maximum_accounts,
-                accounts: Vec::new(),
-                status: 0u8,
-            }
-        }
+    /// Default blocks a deposit intent stays valid before it expires (~1 day)
+    pub const DEFAULT_INTENT_TTL: BlockNumber = 14_400;
 
-        /// Default setup
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new(0u128, 0u16)
-        }
+    /// Default queued transfers expected to be flushed per block
+    pub const DEFAULT_QUEUE_THROUGHPUT: u32 = 16;
 
-        /// Setup escrow
-        #[ink(message)]
-        pub fn setup(&mut self,
-            asset_id: u128,
-            manager: AccountId,
-            maximum_accounts: u16) -> Result<(), Error> {
-            
-            // Setup can only be done by the owner
-            let caller = self.env().caller();
-            if self.env().caller() != self.owner {
-                self.env().emit_event(EscrowEvent {
-                    operator: caller,
-                    status: EscrowStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+    /// Default account operations between two automatic state checkpoints
+    pub const DEFAULT_CHECKPOINT_INTERVAL: u32 = 100;
 
-            // The setup will delete all existing accounts - Very Important!
-            self.asset_id = asset_id;
-            self.manager = manager;
-            self.maximum_accounts = maximum_accounts;
-            self.accounts =  Vec::new();
-            self.status = 0;
+    /// Feature group: disputes and their bond
+    pub const FEATURE_DISPUTES: u32 = 1 << 0;
 
-            self.env().emit_event(EscrowEvent {
-                operator: caller,
-                status: EscrowStatus::EmitSuccess(Success::EscrowSetupSuccess),
-            });
+    /// Feature group: protocol fees
+    pub const FEATURE_FEES: u32 = 1 << 1;
 
-            Ok(())
-        }
+    /// Feature group: manager override releases (`force_release`)
+    pub const FEATURE_FORCE_RELEASE: u32 = 1 << 2;
 
-        /// Get the escrow information
-        #[ink(message)]
-        pub fn get(&self) -> (u128, AccountId, AccountId, u16, u8) {
-            (
-                self.asset_id,
-                self.owner,
-                self.manager,
-                self.maximum_accounts,
-                self.status,
-            )
-        }
+    /// Feature group: vesting release schedules
+    pub const FEATURE_VESTING: u32 = 1 << 3;
 
-        /// Close the escrow service
-        #[ink(message)]
-        pub fn close(&mut self) -> Result<(), Error> {
+    /// Feature group: recipient claims
+    pub const FEATURE_CLAIMS: u32 = 1 << 4;
 
-            // Closing the can only be done by the manager
-            let caller = self.env().caller();
-            if self.env().caller() != self.manager {
-                self.env().emit_event(EscrowEvent {
-                    operator: caller,
-                    status: EscrowStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+    /// All feature groups, the set enabled by `new`
+    pub const FEATURE_ALL: u32 = FEATURE_DISPUTES | FEATURE_FEES | FEATURE_FORCE_RELEASE | FEATURE_VESTING | FEATURE_CLAIMS;
 
-            // This will close the Escrow
-            self.status = 1;
+    /// Pause switch: new accounts, deposits, funding and top-ups
+    pub const PAUSE_ADD: u8 = 1 << 0;
 
-            self.env().emit_event(EscrowEvent {
-                operator: caller,
-                status: EscrowStatus::EmitSuccess(Success::EscrowCloseSuccess),
-            });
+    /// Pause switch: releases by depositors, spenders and sweeps
+    pub const PAUSE_RELEASE: u8 = 1 << 1;
 
-            Ok(())
-        }
+    /// Pause switch: manager override releases
+    pub const PAUSE_FORCE_RELEASE: u8 = 1 << 2;
 
-        /// Open the escrow service
-        #[ink(message)]
-        pub fn open(&mut self) -> Result<(), Error> {
+    /// All pause switches
+    pub const PAUSE_ALL: u8 = PAUSE_ADD | PAUSE_RELEASE | PAUSE_FORCE_RELEASE;
 
-            // Opening the can only be done by the manager
-            let caller = self.env().caller();
-            if self.env().caller() != self.manager {
-                self.env().emit_event(EscrowEvent {
-                    operator: caller,
-                    status: EscrowStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+    /// Gas (ref time) a batch keeps in reserve to finish the message and write its
+    /// storage, the batch stops before the next entry below it
+    pub const BATCH_GAS_RESERVE: u64 = 10_000_000_000;
 
-            // This will open the Escrow
-            self.status = 0;
+    /// Blocks a depositor can pause the release of its own account for (~1 day)
+    pub const ACCOUNT_PAUSE_DURATION: BlockNumber = 14_400;
 
-            self.env().emit_event(EscrowEvent {
-                operator: caller,
-                status: EscrowStatus::EmitSuccess(Success::EscrowOpenSuccess),
-            });
+    /// Default blocks before the end of a timelock `ReleaseDueSoon` is emitted (~1 hour)
+    pub const DEFAULT_RELEASE_NOTICE: BlockNumber = 600;
 
-            Ok(())
-        }   
-        
-        /// Add escrow account, done only by the manager once the transfer of the asset
-        /// us verified through the tx-hash
-        #[ink(message)]
-        pub fn add(&mut self,
-            reference: u16,
-            account: AccountId,
-            amount: u128,
-            recipient: AccountId) -> Result<(), Error> {
+    /// Default blocks between an upgrade proposal and its execution (~3 days)
+    pub const DEFAULT_UPGRADE_DELAY: BlockNumber = 43_200;
 
-            // Adding escrow account can only be done by the manager once the transfer of the 
-            // asset is verified through the tx-hash.
-            let caller = self.env().caller();
-            if self.env().caller() != self.manager {
-                self.env().emit_event(EscrowEvent {
-                    operator: caller,
-                    status: EscrowStatus::EmitError(Error::BadOrigin),
-                });
-                return Ok(());
-            } 
+    /// Storage layout version of this code, bumped with every layout change that
+    /// needs a `migrate` step
+    pub const STORAGE_VERSION: u32 = 1;
 
-            // Check if the escrow is open
-            if self.status != 0 {
-                self.env().emit_event(EscrowEvent {
-                    operator: caller,
-                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
-                });
-                return Ok(());
+    /// Escrow accounts checked for a timelock near its end per account operation
+    pub const NOTICE_SCAN_ACCOUNTS: u16 = 2;
+
+    /// Blocks per outflow counter bucket (~10 minutes)
+    pub const OUTFLOW_BUCKET_BLOCKS: BlockNumber = 100;
+
+    /// Outflow counter buckets kept, the longest window `get_outflow` covers
+    pub const OUTFLOW_BUCKETS: u32 = 144;
+
+    /// Maximum tags per escrow account
+    pub const MAXIMUM_TAGS: usize = 4;
+
+    /// Maximum length of a tag in bytes
+    pub const MAXIMUM_TAG_LENGTH: usize = 32;
+
+    /// Maximum recipient shares per payout template
+    pub const MAXIMUM_TEMPLATE_SHARES: usize = 8;
+
+    /// Maximum third-party contributors per escrow account
+    pub const MAXIMUM_CONTRIBUTORS: usize = 8;
+
+    /// Maximum internal notes per escrow account
+    pub const MAXIMUM_NOTES: usize = 8;
+
+    /// Maximum entries of the deposit intent queue, replaced intents count until
+    /// the purge drops them
+    pub const MAXIMUM_INTENTS: u32 = 1024;
+
+    /// Maximum length of an internal note in bytes
+    pub const MAXIMUM_NOTE_LENGTH: usize = 256;
+
+    /// Rough ref time of a message besides reading the escrow account, used by
+    /// `estimated_weights`
+    pub const MESSAGE_BASE_REF_TIME: [(&str, u64); 9] = [
+        ("add", 3_000_000_000),
+        ("add_auto", 3_500_000_000),
+        ("top_up", 2_500_000_000),
+        ("fund", 4_000_000_000),
+        ("release", 4_000_000_000),
+        ("force_release", 4_000_000_000),
+        ("refund_when_closed", 5_000_000_000),
+        ("transfer_to_escrow", 8_000_000_000),
+        ("flush", 4_000_000_000),
+    ];
+
+    /// Rough ref time spent reading an escrow account mapping entry
+    pub const ACCOUNT_READ_REF_TIME: u64 = 200_000_000;
+
+    /// Rough proof size of a message besides the escrow account it reads
+    pub const MESSAGE_BASE_PROOF_SIZE: u64 = 32_768;
+
+    /// Rough proof size of an escrow account mapping entry with its reference index
+    pub const ACCOUNT_PROOF_SIZE: u64 = 4_096;
+
+    /// Payout template share
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PayoutShare {
+        /// Recipient address
+        pub recipient: AccountId,
+        /// Share of the payout in basis points
+        pub bps: u16,
+    }
+
+    /// Payout template
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PayoutTemplate {
+        /// Template name
+        pub name: String,
+        /// Recipient shares, must add up to 10,000 basis points
+        pub shares: Vec<PayoutShare>,
+    }
+
+    /// Internal note written by the manager on an escrow account
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Note {
+        /// Author of the note
+        pub author: AccountId,
+        /// Note text
+        pub text: String,
+        /// Block number the note was written
+        pub block_number: BlockNumber,
+    }
+
+    /// Third-party contribution to an escrow account
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Contribution {
+        /// Contributor address
+        pub contributor: AccountId,
+        /// Contributed amount
+        pub amount: u128,
+    }
+
+    /// Transfer waiting in the deferred transfer queue
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PendingTransfer {
+        /// Asset to transfer
+        pub asset_id: u128,
+        /// Destination address
+        pub target: AccountId,
+        /// Amount to transfer
+        pub amount: u128,
+        /// Escrow account reference the transfer belongs to
+        pub reference: Option<u16>,
+        /// Failed execution attempts
+        pub attempts: u32,
+    }
+
+    /// Receipt of a manager operation done with an idempotency key, or of a release
+    /// attested with a document hash
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Receipt {
+        /// Operation done
+        pub operation: Success,
+        /// Escrow account reference
+        pub reference: u16,
+        /// Escrow account address
+        pub account: AccountId,
+        /// Amount added or released
+        pub amount: u128,
+        /// Block number of the operation
+        pub block_number: BlockNumber,
+    }
+
+    /// Manager operation an idempotency key is used for, keys of different
+    /// operations do not collide
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum KeyedOperation {
+        /// `add` and `add_auto`
+        Add,
+        /// `top_up`
+        TopUp,
+        /// `force_release` and its split and partial variants
+        ForceRelease,
+    }
+
+    /// Receipt key (operator, operation, idempotency key)
+    pub type ReceiptKey = (AccountId, KeyedOperation, Hash);
+
+    /// Position NFT approval key (owner, operator, token id), no token id approves
+    /// all the positions of the owner
+    pub type ApprovalKey = (AccountId, AccountId, Option<Id>);
+
+    /// Spender allowance on the escrow account of a depositor (reference it was
+    /// granted on, remaining amount)
+    pub type Allowance = (u16, u128);
+
+    /// Deposit intent registered by a depositor before funding
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Intent {
+        /// Unique reference from the dApp
+        pub reference: u16,
+        /// Amount the depositor will fund
+        pub amount: u128,
+        /// Recipient address
+        pub recipient: AccountId,
+        /// Block number the intent was registered
+        pub registered_at: BlockNumber,
+    }
+
+    /// Rough weight estimate of a message
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct WeightEstimate {
+        /// Message name
+        pub message: String,
+        /// Estimated ref time in picoseconds
+        pub ref_time: u64,
+        /// Estimated proof size in bytes
+        pub proof_size: u64,
+    }
+
+    /// Dispute pending on an escrow account
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Dispute {
+        /// Depositor or recipient that raised the dispute
+        pub raiser: AccountId,
+        /// Dispute bond posted by the raiser
+        pub bond: u128,
+        /// Block number the dispute was raised
+        pub raised_at: BlockNumber,
+        /// Arbiter drawn from the arbiter registry, the only one ruling the dispute
+        pub arbiter: Option<AccountId>,
+    }
+
+    /// Linear vesting schedule of an escrow account, the vested part of the amount
+    /// ever escrowed (`released` plus the balance) grows from `start` to
+    /// `start + duration`, nothing vests before `start + cliff`
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Vesting {
+        /// Block number the vesting starts
+        pub start: BlockNumber,
+        /// Blocks until the whole amount is vested
+        pub duration: BlockNumber,
+        /// Blocks after the start before anything vests
+        pub cliff: BlockNumber,
+        /// Amount already released under the schedule
+        pub released: u128,
+    }
+
+    /// Approvals of a mutual-consent release, the escrow account is released once
+    /// both parties approved
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct ReleaseConsent {
+        /// Depositor approved the release
+        pub depositor: bool,
+        /// Recipient approved the release
+        pub recipient: bool,
+    }
+
+    /// Arbiter ruling on a dispute
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum DisputeOutcome {
+        /// Whole balance to the recipient
+        Release,
+        /// Whole balance back to the depositor and the other contributors
+        Refund,
+        /// Basis points of the balance to the recipient, the rest refunded
+        Split(u16),
+    }
+
+    impl DisputeOutcome {
+        /// Basis points of the balance the outcome pays to the recipient
+        pub fn recipient_bps(&self) -> u16 {
+            match self {
+                DisputeOutcome::Release => 10_000,
+                DisputeOutcome::Refund => 0,
+                DisputeOutcome::Split(bps) => *bps,
             }
+        }
+    }
 
-            // Check if there is a duplicate escrow account
-            for a in self.accounts.iter_mut() {
-                if a.account == account {
-                    self.env().emit_event(EscrowEvent {
-                        operator: caller,
-                        status: EscrowStatus::EmitError(Error::EscrowAccountDuplicate),
-                    });
-                    return Ok(());
-                }
+    /// Ruling of a panel member on a dispute
+    pub type Ruling = (AccountId, DisputeOutcome);
+
+    /// Ruling record of an arbiter
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct ArbiterStats {
+        /// Rulings submitted on settled disputes
+        pub rulings: u32,
+        /// Rulings matching the executed outcome
+        pub rewarded: u32,
+        /// Rulings overruled by the panel
+        pub slashed: u32,
+    }
+
+    /// Order in which a sweep pays the matured escrow accounts out of constrained
+    /// liquidity
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum ReleaseOrder {
+        /// Oldest account first, by add block
+        Fifo,
+        /// Smallest balance first, paying out the most accounts
+        SmallestFirst,
+        /// References of the manager priority list first in list order, then the
+        /// oldest accounts
+        Priority,
+    }
+
+    /// Release simulation result
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SimResult {
+        /// Escrow account address
+        pub account: AccountId,
+        /// Amount that would be released, the vested part of a vesting account,
+        /// fee included
+        pub amount: u128,
+        /// Protocol fee the collector would receive out of the amount
+        pub fee: u128,
+        /// Recipient of the transfer
+        pub recipient: Option<Recipient>,
+        /// Error that would stop the release
+        pub error: Option<Error>,
+    }
+
+    /// Result of one entry of a batch operation
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct BatchResult {
+        /// Escrow account address
+        pub account: AccountId,
+        /// Error that stopped the entry
+        pub error: Option<Error>,
+    }
+
+    /// Outcome of a batch operation
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct BatchOutcome {
+        /// Results of the processed entries, in order
+        pub results: Vec<BatchResult>,
+        /// Index of the first unprocessed entry if the batch stopped before running
+        /// out of gas
+        pub cursor: Option<u32>,
+    }
+
+    /// Escrowed `pallet_nfts` item, released whole to its recipient
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct NftEscrow {
+        /// Reference, unique across fungible and NFT escrows
+        pub reference: u16,
+        /// Depositor of the item
+        pub account: AccountId,
+        /// `pallet_nfts` collection id
+        pub collection: u32,
+        /// `pallet_nfts` item id
+        pub item: u32,
+        /// Recipient of the item
+        pub recipient: AccountId,
+        /// Block number the NFT escrow was added
+        pub added_at: BlockNumber,
+    }
+
+    /// Backend holding the escrow asset
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum TransferBackend {
+        /// `pallet_assets` asset `asset_id`, moved through `call_runtime`
+        Assets,
+        /// PSP22 token contract, moved through cross-contract calls
+        Psp22(AccountId),
+        /// Native token of the chain, deposited with the call value and moved through
+        /// `pallet_balances`
+        Native,
+    }
+
+    /// `pallet_assets` call moving the escrow asset out of the escrow
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum TransferMethod {
+        /// `transfer`, the escrow asset account may be reaped below the minimum
+        /// balance
+        Transfer,
+        /// `transfer_keep_alive`, a transfer that would reap the escrow asset account
+        /// fails instead
+        KeepAlive,
+        /// `transfer_all` for a payout of the whole asset balance of the escrow (read
+        /// with balance checks), which closes the asset account without leftovers,
+        /// `transfer_keep_alive` for any other payout
+        TransferAll,
+    }
+
+    /// Manager override release waiting for the owner countersignature
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct PendingRelease {
+        /// Manager that initiated the release
+        pub manager: AccountId,
+        /// Escrow account released
+        pub account: AccountId,
+        /// Recipient of the release
+        pub recipient: AccountId,
+        /// Basis points of the balance for the recipient, the depositor gets the rest
+        pub recipient_bps: u16,
+        /// Amount of a partial release, the whole balance is split otherwise
+        pub amount: Option<u128>,
+        /// Idempotency key of the release
+        pub key: Option<Hash>,
+        /// Block number the manager initiated the release
+        pub initiated_at: BlockNumber,
+        /// The release converts a stuck account into a refund to the depositor
+        pub refund: bool,
+    }
+
+    /// Performance counters of a manager
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct ManagerMetrics {
+        /// Escrow accounts added
+        pub accounts_added: u32,
+        /// Releases approved through `force_release`
+        pub releases_approved: u32,
+        /// Blocks from add to approval, summed over the approved releases
+        pub approval_blocks: BlockNumber,
+        /// Average blocks from add to approval
+        pub average_approval_blocks: BlockNumber,
+        /// Disputes ruled against the manager
+        pub disputes_lost: u32,
+    }
+
+    /// Operations summary
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct OpsSummary {
+        /// Status (0-Open, 1-Close)
+        pub status: u8,
+        /// Number of escrow accounts
+        pub accounts: u16,
+        /// Number of frozen escrow accounts
+        pub frozen_accounts: u16,
+        /// Number of liquid escrow accounts
+        pub liquid_accounts: u16,
+        /// Number of escrow accounts with a pending dispute
+        pub disputed_accounts: u16,
+        /// Number of escrow accounts waiting for their recipient to accept them
+        pub pending_accounts: u16,
+        /// Sum of all escrow account balances
+        pub total_liability: u128,
+        /// Manager bond posted
+        pub bond: u128,
+        /// Number of transfers waiting in the deferred transfer queue
+        pub queued_transfers: u32,
+        /// Escrow asset amount waiting in the deferred transfer queue
+        pub queued_amount: u128,
+        /// Releases are paused after an insolvency was detected
+        pub insolvency_paused: bool,
+    }
+
+    /// Escrow statistics for treasury dashboards
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Stats {
+        /// Escrow asset locked in the accounts
+        pub total_balance: u128,
+        /// Number of escrow accounts
+        pub accounts: u16,
+        /// Number of frozen escrow accounts
+        pub frozen_accounts: u16,
+        /// Number of liquid escrow accounts
+        pub liquid_accounts: u16,
+        /// Number of escrow accounts with a pending dispute
+        pub disputed_accounts: u16,
+        /// Escrow asset released since deployment
+        pub total_released: u128,
+    }
+
+    /// Escrow storage
+    #[ink(storage)]
+    pub struct Escrow {
+        /// Escrow asset
+        pub asset_id: u128,
+        /// Escrow owner
+        pub owner: AccountId,
+        /// Escrow manager
+        pub manager: AccountId,
+        /// Maximum accounts the escrow can handle
+        pub maximum_accounts: u16,
+        /// Escrow accounts by depositor
+        pub accounts: Mapping<AccountId, Account>,
+        /// Status (0-Open, 1-Close)
+        pub status: u8,
+        /// Bond the manager must post before the escrow can open
+        pub bond_required: u128,
+        /// Bond posted
+        pub bond: u128,
+        /// Account that posted the bond
+        pub bond_owner: AccountId,
+        /// Bounded activity history per reference
+        pub history: Mapping<u16, Vec<History>>,
+        /// Next reference assigned by `add_auto`
+        pub reference_counter: u16,
+        /// What a close blocks (0-Adds and releases, 1-Adds only, 2-Releases only)
+        pub close_policy: u8,
+        /// Block number the escrow was closed
+        pub closed_at: BlockNumber,
+        /// Blocks the escrow must stay close before depositors can refund
+        pub refund_delay: BlockNumber,
+        /// Recipients must confirm their address before the account is releasable
+        pub recipient_confirmation: bool,
+        /// Payout templates
+        pub templates: Mapping<u16, PayoutTemplate>,
+        /// New escrow accounts are minted as PSP34 position NFTs
+        pub nft_positions: bool,
+        /// Position NFT approvals (owner, operator, token id)
+        pub nft_approvals: Mapping<ApprovalKey, ()>,
+        /// Third-party contributions per reference
+        pub contributions: Mapping<u16, Vec<Contribution>>,
+        /// Escrow contracts allowed to transfer positions into this escrow
+        pub accepted_escrows: Mapping<AccountId, ()>,
+        /// Native balance pre-funded for future storage growth
+        pub storage_funds: Balance,
+        /// Transfers are queued and executed later through `flush`
+        pub deferred_transfers: bool,
+        /// Deferred transfer queue
+        pub transfer_queue: Mapping<u32, PendingTransfer>,
+        /// Index of the first queued transfer
+        pub queue_head: u32,
+        /// Index after the last queued transfer
+        pub queue_tail: u32,
+        /// Escrow asset amount waiting in the queue
+        pub queued_amount: u128,
+        /// Receipts of manager operations by operator, operation and idempotency key
+        pub receipts: Mapping<ReceiptKey, Receipt>,
+        /// Releases are paused after an insolvency was detected
+        pub insolvency_paused: bool,
+        /// Blocks after an account is added during which a dispute may be raised, part
+        /// of the default terms
+        pub dispute_window: BlockNumber,
+        /// `add` only confirms intents registered by the depositors
+        pub require_intents: bool,
+        /// Deposit intents per depositor
+        pub intents: Mapping<AccountId, Intent>,
+        /// Intent registrations in registration order with their block, the queue
+        /// walked by the purge
+        pub intent_queue: Mapping<u32, (AccountId, BlockNumber)>,
+        /// Index of the oldest registration in the intent queue
+        pub intent_head: u32,
+        /// Index the next registration is queued at
+        pub intent_tail: u32,
+        /// Blocks a deposit intent stays valid after its registration
+        pub intent_ttl: BlockNumber,
+        /// Payout address set by a recipient to redirect its own payouts
+        pub payout_addresses: Mapping<AccountId, AccountId>,
+        /// Auditor allowed to read the internal notes
+        pub auditor: AccountId,
+        /// Internal notes per reference
+        pub notes: Mapping<u16, Vec<Note>>,
+        /// Queued transfers expected to be flushed per block, used for queue estimates
+        pub queue_throughput: u32,
+        /// Performance counters per manager
+        pub manager_metrics: Mapping<AccountId, ManagerMetrics>,
+        /// Bond a dispute raiser must post, refunded if the raiser wins and forfeited
+        /// to the counterparty if it loses
+        pub dispute_bond: u128,
+        /// Minimum balance of the escrow asset, account transfers below it are dust
+        pub dust_threshold: u128,
+        /// Dust policy (0 = transfer anyway, 1 = keep in the dust pot)
+        pub dust_policy: u8,
+        /// Escrow asset dust kept by the contract, withdrawable by the owner
+        pub dust_pot: u128,
+        /// Depositor of each escrow account by reference
+        pub references: Mapping<u16, AccountId>,
+        /// Number of escrow accounts
+        pub account_count: u16,
+        /// Depositors by slot, slots `0..account_count` enumerate the escrow accounts
+        pub account_slots: Mapping<u16, AccountId>,
+        /// Slot of each depositor in `account_slots`
+        pub slot_of: Mapping<AccountId, u16>,
+        /// Sum of the escrow account balances in the escrow asset
+        pub total_balance: u128,
+        /// Account operations between two automatic state checkpoints (0 = manual only)
+        pub checkpoint_interval: u32,
+        /// Account operations since the last state checkpoint
+        pub operations_since_checkpoint: u32,
+        /// Number of state checkpoints emitted
+        pub checkpoint_counter: u32,
+        /// Feature groups enabled at instantiation (`FEATURE_*` bitflags)
+        pub features: u32,
+        /// Companion registry the escrow is registered with
+        pub registry: Option<AccountId>,
+        /// Blocks before the end of a timelock `ReleaseDueSoon` is emitted, 0 only
+        /// emits it when the timelock is set
+        pub release_notice: BlockNumber,
+        /// Slot of the next escrow account checked for a timelock near its end
+        pub notice_cursor: u16,
+        /// Due block already announced per reference
+        pub notified_due: Mapping<u16, BlockNumber>,
+        /// Owner nominated by `transfer_ownership`, waiting for `accept_ownership`
+        pub pending_owner: Option<AccountId>,
+        /// Manager nominated by `set_manager`, waiting for `accept_manager`
+        pub pending_manager: Option<AccountId>,
+        /// `close` fails while any escrow account still holds a balance
+        pub close_requires_empty: bool,
+        /// Blocks the owner has to countersign a manager override release, 0 = no
+        /// dual control
+        pub dual_control_window: BlockNumber,
+        /// Manager override releases waiting for the owner countersignature
+        pub pending_releases: Mapping<u32, PendingRelease>,
+        /// Id of the next pending release
+        pub pending_release_counter: u32,
+        /// Escrow asset paid out of the accounts per bucket of `OUTFLOW_BUCKET_BLOCKS`
+        /// blocks, a ring of `OUTFLOW_BUCKETS` slots holding (bucket, amount)
+        pub outflow_buckets: Mapping<u32, (BlockNumber, u128)>,
+        /// Running sum of the account balances per asset other than the escrow asset,
+        /// which `total_balance` covers
+        pub asset_balances: Mapping<u128, u128>,
+        /// Lock period of the accounts added without an explicit one, part of the
+        /// default terms
+        pub default_lock_period: BlockNumber,
+        /// Blocks after an account is added during which it may be cancelled, part of
+        /// the default terms
+        pub cancel_window: BlockNumber,
+        /// Backend holding the escrow asset, fixed at instantiation
+        pub backend: TransferBackend,
+        /// Remaining allowance of a spender on the escrow account of a depositor,
+        /// keyed by (depositor, spender), with the reference it was granted on
+        pub spender_allowances: Mapping<(AccountId, AccountId), Allowance>,
+        /// Escrowed `pallet_nfts` items by reference
+        pub nft_escrows: Mapping<u16, NftEscrow>,
+        /// Roles granted by the owner, keyed by (role, account)
+        pub roles: Mapping<(Role, AccountId), ()>,
+        /// Managers approving override releases under the quorum
+        pub quorum_managers: Vec<AccountId>,
+        /// Approvals of quorum managers an override release needs, 0 = no quorum
+        pub quorum_threshold: u8,
+        /// Blocks the quorum managers have to approve an override release
+        pub quorum_window: BlockNumber,
+        /// Quorum managers that approved a pending release
+        pub release_approvals: Mapping<u32, Vec<AccountId>>,
+        /// Order of the matured-release sweep
+        pub release_order: ReleaseOrder,
+        /// References paid first under `ReleaseOrder::Priority`
+        pub release_priority: Vec<u16>,
+        /// Pending disputes by reference
+        pub disputes: Mapping<u16, Dispute>,
+        /// Dispute bonds posted on the pending disputes
+        pub dispute_bonds: u128,
+        /// Protocol fee taken off releases, in basis points
+        pub fee_bps: u16,
+        /// Account receiving the protocol fees
+        pub fee_collector: AccountId,
+        /// Escrow asset released since deployment
+        pub total_released: u128,
+        /// Blocks between an upgrade proposal and its execution
+        pub upgrade_delay: BlockNumber,
+        /// Proposed code hash and the block from which it can be set
+        pub pending_upgrade: Option<(Hash, BlockNumber)>,
+        /// Storage layout version of the stored state
+        pub version: u32,
+        /// Number of NFT escrows
+        pub nft_count: u16,
+        /// Pause switches of single operations (`PAUSE_*` bitflags)
+        pub paused: u8,
+        /// Only depositors on the allow-list enter the escrow
+        pub allow_list_enabled: bool,
+        /// Depositors allowed to enter the escrow
+        pub allow_list: Mapping<AccountId, bool>,
+        /// Recipients funds must never be sent to
+        pub blocked_recipients: Mapping<AccountId, ()>,
+        /// Next release signature nonce of each depositor
+        pub release_nonces: Mapping<AccountId, u64>,
+        /// Vesting schedules by reference
+        pub vestings: Mapping<u16, Vesting>,
+        /// Release approvals of the mutual-consent escrow accounts by reference
+        pub release_consents: Mapping<u16, ReleaseConsent>,
+        /// Recipients must accept the accounts added for them before they are active
+        pub recipient_acceptance: bool,
+        /// Releases read the asset balance of the escrow from its backend first
+        pub balance_checks: bool,
+        /// Reconciliation pauses adds when it finds the escrow insolvent
+        pub reconcile_pauses_adds: bool,
+        /// `pallet_assets` call used to pay out of the escrow
+        pub transfer_method: TransferMethod,
+        /// Pallet and call indices of the runtime, fixed at instantiation
+        pub pallet_indices: PalletIndices,
+        /// Block of the last failed release of an escrow account, such an account
+        /// may be converted into a refund
+        pub release_failures: Mapping<AccountId, BlockNumber>,
+        /// Receipts of the attested releases by document hash
+        pub attestations: Mapping<Hash, Receipt>,
+        /// Arbiters ruling the disputes together (3 or 5), empty = any holder of the
+        /// `Arbiter` role rules alone
+        pub arbiter_panel: Vec<AccountId>,
+        /// Rulings submitted by the panel on the pending disputes by reference
+        pub dispute_rulings: Mapping<u16, Vec<Ruling>>,
+        /// Ruling records of the arbiters
+        pub arbiter_stats: Mapping<AccountId, ArbiterStats>,
+        /// External registry drawing the arbiter of each dispute
+        pub arbiter_registry: Option<AccountId>,
+    }
+
+
+    /// Message selectors are pinned to the value derived from the original message
+    /// name, renamed messages keep a thin legacy wrapper under the old selector.
+    impl Escrow {
+
+        /// Create new escrow service
+        #[ink(constructor, selector = 0x9BAE9D5E)]
+        pub fn new(asset_id: u128, 
+            maximum_accounts: u16) -> Self {
+
+            let caller: ink::primitives::AccountId = Self::env().caller();
+
+            Self { 
+                asset_id, 
+                owner: caller,
+                manager: caller,
+                maximum_accounts,
+                accounts: Mapping::default(),
+                status: 0u8,
+                bond_required: 0u128,
+                bond: 0u128,
+                bond_owner: caller,
+                history: Mapping::default(),
+                reference_counter: 0u16,
+                close_policy: 0u8,
+                closed_at: 0,
+                refund_delay: DEFAULT_REFUND_DELAY,
+                recipient_confirmation: false,
+                templates: Mapping::default(),
+                nft_positions: false,
+                nft_approvals: Mapping::default(),
+                contributions: Mapping::default(),
+                accepted_escrows: Mapping::default(),
+                storage_funds: 0,
+                deferred_transfers: false,
+                transfer_queue: Mapping::default(),
+                queue_head: 0,
+                queue_tail: 0,
+                queued_amount: 0,
+                receipts: Mapping::default(),
+                insolvency_paused: false,
+                dispute_window: DEFAULT_DISPUTE_WINDOW,
+                require_intents: false,
+                intents: Mapping::default(),
+                intent_queue: Mapping::default(),
+                intent_head: 0,
+                intent_tail: 0,
+                intent_ttl: DEFAULT_INTENT_TTL,
+                payout_addresses: Mapping::default(),
+                auditor: caller,
+                notes: Mapping::default(),
+                queue_throughput: DEFAULT_QUEUE_THROUGHPUT,
+                manager_metrics: Mapping::default(),
+                dispute_bond: 0,
+                dust_threshold: 0,
+                dust_policy: 0,
+                dust_pot: 0,
+                references: Mapping::default(),
+                account_count: 0,
+                account_slots: Mapping::default(),
+                slot_of: Mapping::default(),
+                total_balance: 0,
+                checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+                operations_since_checkpoint: 0,
+                checkpoint_counter: 0,
+                features: FEATURE_ALL,
+                registry: None,
+                release_notice: DEFAULT_RELEASE_NOTICE,
+                notice_cursor: 0,
+                notified_due: Mapping::default(),
+                pending_owner: None,
+                pending_manager: None,
+                close_requires_empty: false,
+                dual_control_window: 0,
+                pending_releases: Mapping::default(),
+                pending_release_counter: 0,
+                outflow_buckets: Mapping::default(),
+                asset_balances: Mapping::default(),
+                default_lock_period: 0,
+                cancel_window: 0,
+                backend: TransferBackend::Assets,
+                spender_allowances: Mapping::default(),
+                nft_escrows: Mapping::default(),
+                roles: Mapping::default(),
+                quorum_managers: Vec::new(),
+                quorum_threshold: 0,
+                quorum_window: 0,
+                release_approvals: Mapping::default(),
+                release_order: ReleaseOrder::Fifo,
+                release_priority: Vec::new(),
+                disputes: Mapping::default(),
+                dispute_bonds: 0,
+                fee_bps: 0,
+                fee_collector: caller,
+                total_released: 0,
+                upgrade_delay: DEFAULT_UPGRADE_DELAY,
+                pending_upgrade: None,
+                version: STORAGE_VERSION,
+                nft_count: 0,
+                paused: 0,
+                allow_list_enabled: false,
+                allow_list: Mapping::default(),
+                blocked_recipients: Mapping::default(),
+                release_nonces: Mapping::default(),
+                vestings: Mapping::default(),
+                release_consents: Mapping::default(),
+                recipient_acceptance: false,
+                balance_checks: false,
+                reconcile_pauses_adds: false,
+                transfer_method: TransferMethod::Transfer,
+                pallet_indices: PalletIndices::default(),
+                release_failures: Mapping::default(),
+                attestations: Mapping::default(),
+                arbiter_panel: Vec::new(),
+                dispute_rulings: Mapping::default(),
+                arbiter_stats: Mapping::default(),
+                arbiter_registry: None,
             }
+        }
 
-            // Add the escrow account
-            if self.accounts.len() as u16 >= self.maximum_accounts {
+        /// Create new escrow service with only some feature groups enabled
+        /// (`FEATURE_*` bitflags), the set is fixed for the lifetime of the deployment
+        #[ink(constructor, selector = 0x11AE2634)]
+        pub fn new_with_features(asset_id: u128,
+            maximum_accounts: u16,
+            features: u32) -> Self {
+
+            let mut escrow = Self::new(asset_id, maximum_accounts);
+            escrow.features = features & FEATURE_ALL;
+            escrow
+        }
+
+        /// Create new escrow service for a runtime ordering its pallets or their calls
+        /// differently from the default layout (Assets 50, Balances 10, PolkadotXcm
+        /// 31, Utility 40, Nfts 52), so the same artifact deploys on any runtime
+        #[ink(constructor, selector = 0xB28895EB)]
+        pub fn new_for_runtime(asset_id: u128,
+            maximum_accounts: u16,
+            features: u32,
+            pallet_indices: PalletIndices) -> Self {
+
+            let mut escrow = Self::new_with_features(asset_id, maximum_accounts, features);
+            escrow.pallet_indices = pallet_indices;
+            escrow
+        }
+
+        /// Create new escrow service registered with a companion registry, the
+        /// instantiation fails if the registry rejects it
+        #[ink(constructor, selector = 0xA27B6D17)]
+        pub fn new_with_registry(asset_id: u128,
+            maximum_accounts: u16,
+            features: u32,
+            registry: AccountId) -> Result<Self, Error> {
+
+            let mut escrow = Self::new_with_features(asset_id, maximum_accounts, features);
+            escrow.register_with(registry)?;
+            Ok(escrow)
+        }
+
+        /// Create new escrow service holding its escrow asset in a PSP22 token
+        /// contract instead of `pallet_assets`, `asset_id` only labels the escrow
+        /// asset of the accounts
+        #[ink(constructor, selector = 0xFAE69DEF)]
+        pub fn new_with_psp22(asset_id: u128,
+            maximum_accounts: u16,
+            token: AccountId) -> Self {
+
+            let mut escrow = Self::new(asset_id, maximum_accounts);
+            escrow.backend = TransferBackend::Psp22(token);
+            escrow
+        }
+
+        /// Default setup
+        #[ink(constructor, selector = 0xED4B9D1B)]
+        #[allow(clippy::should_implement_trait)]
+        pub fn default() -> Self {
+            Self::new(0u128, 0u16)
+        }
+
+        /// Setup escrow. A legacy message, a rejected setup returns `Ok` and reports
+        /// the error through the event only.
+        #[ink(message, selector = 0x86A08581)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn setup(&mut self,
+            asset_id: u128,
+            manager: AccountId,
+            maximum_accounts: u16,
+            bond_required: u128,
+            close_policy: u8,
+            refund_delay: BlockNumber,
+            dispute_window: BlockNumber,
+            close_requires_empty: bool) -> Result<(), Error> {
+            
+            // Setup can only be done by the owner
+            let caller = self.env().caller();
+            if self.env().caller() != self.owner {
                 self.env().emit_event(EscrowEvent {
                     operator: caller,
-                    status: EscrowStatus::EmitError(Error::EscrowAccountMax),
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
                 });
                 return Ok(());
-            }
+            } 
 
-            let new_account = Account {
-                reference,
-                account,
-                balance: amount,
-                recipient,
-                status: 1, // 1 = Liquid
-            };
-            
-            self.accounts.push(new_account);
+            // The setup will delete all existing accounts - Very Important!
+            self.asset_id = asset_id;
+            self.manager = manager;
+            self.pending_manager = None;
+            self.maximum_accounts = maximum_accounts;
+            self.clear_accounts();
+            self.bond_required = bond_required;
+            self.close_policy = close_policy;
+            self.refund_delay = refund_delay;
+            self.dispute_window = dispute_window;
+            self.close_requires_empty = close_requires_empty;
+
+            // The escrow stays close until the manager bond is posted
+            if self.bond < self.bond_required {
+                self.status = 1;
+                self.closed_at = self.env().block_number();
+            } else {
+                self.status = 0;
+            }
 
             self.env().emit_event(EscrowEvent {
                 operator: caller,
-                status: EscrowStatus::EmitSuccess(Success::EscrowAccountAdded),
+                status: EscrowStatus::EmitSuccess(Success::EscrowSetupSuccess),
+            });
+            self.env().emit_event(Setup {
+                asset_id,
+                manager,
+                maximum_accounts,
+                bond_required,
             });
 
+            self.assert_invariants();
+
             Ok(())
         }
 
-        /// Released the escrow account balance to the recipient
-        #[ink(message)]
-        pub fn release(&mut self) -> Result<(), ContractError> {
+        /// Set the maximum number of escrow accounts without touching the accounts,
+        /// 0 lifts the limit. It cannot drop below the open accounts.
+        #[ink(message, selector = 0xE1673078)]
+        pub fn set_maximum_accounts(&mut self, maximum_accounts: u16) -> Result<(), Error> {
 
-            // Release an escrow account by the caller
+            // Only the owner can change the escrow configuration
             let caller = self.env().caller();
-
-            // Check if the escrow is open
-            if self.status != 0 {
+            if caller != self.owner {
                 self.env().emit_event(EscrowEvent {
                     operator: caller,
-                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
                 });
-                return Ok(());
+                return Err(Error::BadOrigin);
             }
 
-            // Locate the account of the caller and delete it from the escrow 
-            for i in 0..self.accounts.len() {
-
-                if self.accounts[i].account == caller {
-                    // Transfer funds - Todo
-                    self.env()
-                        .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                            id: self.asset_id,
-                            target: self.accounts[i].recipient.into(),
-                            amount: self.accounts[i].balance,
-                        }))
-                        .map_err(|_| RuntimeError::CallRuntimeFailed)?;                    
-
-                    // Remove escrow account (gas efficient)
-                    self.accounts.swap_remove(i);
-
-
-                    self.env().emit_event(EscrowEvent {
-                        operator: caller,
-                        status: EscrowStatus::EmitSuccess(Success::EscrowAccountReleased),
-                    });
+            if maximum_accounts != 0 && maximum_accounts < self.account_count {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountMax),
+                });
+                return Err(Error::EscrowAccountMax);
+            }
 
-                    return Ok(());
-                }
-            }            
+            self.maximum_accounts = maximum_accounts;
 
             self.env().emit_event(EscrowEvent {
                 operator: caller,
-                status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
             });
 
+            self.assert_invariants();
+
             Ok(())
         }
 
-        /// Override, this will release the escrow account to some recipient
-        #[ink(message)]
-        pub fn force_release(&mut self,
-            account: AccountId,
-            recipient: AccountId) -> Result<(), ContractError> {
+        /// Set the escrow asset without touching the configuration, only while the
+        /// escrow holds no accounts
+        #[ink(message, selector = 0x4100B54A)]
+        pub fn set_asset_id(&mut self, asset_id: u128) -> Result<(), Error> {
 
-            // Override the release of the escrow account can only be done by 
-            // the manager.
+            // Only the owner can change the escrow configuration
             let caller = self.env().caller();
-            if self.env().caller() != self.manager {
+            if caller != self.owner {
                 self.env().emit_event(EscrowEvent {
                     operator: caller,
                     status: EscrowStatus::EmitError(Error::BadOrigin),
                 });
-                return Ok(());
-            } 
+                return Err(Error::BadOrigin);
+            }
 
-            // Check if the escrow is open
-            if self.status != 0 {
+            // Open accounts are denominated in the current asset
+            if self.account_count > 0 {
                 self.env().emit_event(EscrowEvent {
                     operator: caller,
-                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                    status: EscrowStatus::EmitError(Error::EscrowIsNotEmpty),
                 });
-                return Ok(());
+                return Err(Error::EscrowIsNotEmpty);
             }
-            
-            // Locate the account of the caller and delete it from the escrow 
-            for i in 0..self.accounts.len() {
-
-                if self.accounts[i].account == account {
-                    // Transfer funds - Todo (Recipient must be manually provided)
-                    self.env()
-                        .call_runtime(&RuntimeCall::Assets(AssetsCall::Transfer {
-                            id: self.asset_id,
-                            target: recipient.into(),
-                            amount: self.accounts[i].balance,
-                        }))
-                        .map_err(|_| RuntimeError::CallRuntimeFailed)?;  
-
-                    // Remove escrow account (gas efficient)
-                    self.accounts.swap_remove(i);
-
-                    self.env().emit_event(EscrowEvent {
-                        operator: caller,
-                        status: EscrowStatus::EmitSuccess(Success::EscrowAccountReleased),
-                    });
 
-                    return Ok(());
-                }
-            }            
+            self.asset_id = asset_id;
 
             self.env().emit_event(EscrowEvent {
                 operator: caller,
-                status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
             });
 
-            Ok(())            
+            self.assert_invariants();
+
+            Ok(())
         }
 
+        /// Get the escrow information
+        #[ink(message, selector = 0x0190E5AC)]
+        pub fn info(&self) -> (u128, AccountId, AccountId, u16, u8) {
+            (
+                self.asset_id,
+                self.owner,
+                self.manager,
+                self.maximum_accounts,
+                self.status,
+            )
+        }
+
+        /// Legacy name of `info`, kept so existing encoded calls keep working
+        #[ink(message, selector = 0x2F865BD9)]
+        pub fn get(&self) -> (u128, AccountId, AccountId, u16, u8) {
+            self.info()
+        }
+
+        /// Get rough weight estimates of the main messages, so clients can set gas
+        /// limits. Escrow accounts are read one mapping entry at a time, the estimates
+        /// no longer follow the book size.
+        #[ink(message, selector = 0x8CDB8439)]
+        pub fn estimated_weights(&self) -> Vec<WeightEstimate> {
+            MESSAGE_BASE_REF_TIME
+                .iter()
+                .map(|(message, ref_time)| WeightEstimate {
+                    message: String::from(*message),
+                    ref_time: ref_time.saturating_add(ACCOUNT_READ_REF_TIME),
+                    proof_size: MESSAGE_BASE_PROOF_SIZE.saturating_add(ACCOUNT_PROOF_SIZE),
+                })
+                .collect()
+        }
+
+        /// Get the operations summary in a single read
+        #[ink(message, selector = 0xB8125DA3)]
+        pub fn ops_summary(&self) -> OpsSummary {
+            let mut summary = OpsSummary {
+                status: self.status,
+                accounts: self.account_count,
+                frozen_accounts: 0,
+                liquid_accounts: 0,
+                disputed_accounts: 0,
+                pending_accounts: 0,
+                total_liability: self.total_balance,
+                bond: self.bond,
+                queued_transfers: self.queue_tail.wrapping_sub(self.queue_head),
+                queued_amount: self.queued_amount,
+                insolvency_paused: self.insolvency_paused,
+            };
+
+            for a in self.account_list() {
+                match a.status {
+                    0 => summary.frozen_accounts += 1,
+                    2 => summary.disputed_accounts += 1,
+                    3 => summary.pending_accounts += 1,
+                    _ => summary.liquid_accounts += 1,
+                }
+            }
+
+            summary
+        }
+
+        /// Get the total value locked, the account counts and the escrow asset
+        /// released since deployment
+        #[ink(message, selector = 0x377C5444)]
+        pub fn stats(&self) -> Stats {
+            let summary = self.ops_summary();
+            Stats {
+                total_balance: self.total_balance,
+                accounts: summary.accounts,
+                frozen_accounts: summary.frozen_accounts,
+                liquid_accounts: summary.liquid_accounts,
+                disputed_accounts: summary.disputed_accounts,
+                total_released: self.total_released,
+            }
+        }
+
+        /// Emit a state checkpoint for indexers
+        #[ink(message, selector = 0x27227304)]
+        pub fn checkpoint(&mut self) -> Result<(), Error> {
+
+            // Only the manager can emit a checkpoint on demand
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.emit_checkpoint();
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowCheckpointEmitted {
+                    checkpoint: self.checkpoint_counter,
+                }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the account operations between two automatic state checkpoints, 0 only
+        /// emits them through `checkpoint`
+        #[ink(message, selector = 0x55F20566)]
+        pub fn set_checkpoint_interval(&mut self, interval: u32) -> Result<(), Error> {
+
+            // Only the owner can change the checkpoint schedule
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.checkpoint_interval = interval;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the Merkle root of the escrow accounts. The leaves are the Blake2x256
+        /// hashes of the SCALE-encoded accounts in slot order, pairs are hashed
+        /// left to right and an odd node is carried up unchanged. No accounts give the
+        /// zero hash.
+        #[ink(message, selector = 0x160C05DD)]
+        pub fn accounts_root(&self) -> Hash {
+            let mut level: Vec<[u8; 32]> = self.account_list()
+                .iter()
+                .map(|a| self.env().hash_encoded::<Blake2x256, _>(a))
+                .collect();
+            if level.is_empty() {
+                return Hash::default();
+            }
+
+            while level.len() > 1 {
+                level = level
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [left, right] => self.env().hash_bytes::<Blake2x256>(&[&left[..], &right[..]].concat()),
+                        _ => pair[0],
+                    })
+                    .collect();
+            }
+
+            Hash::from(level[0])
+        }
+
+        /// Count an account operation, emitting a state checkpoint every
+        /// `checkpoint_interval` operations
+        fn count_operation(&mut self) {
+            self.operations_since_checkpoint = self.operations_since_checkpoint.saturating_add(1);
+            if self.checkpoint_interval != 0 && self.operations_since_checkpoint >= self.checkpoint_interval {
+                self.emit_checkpoint();
+            }
+            self.notify_due_releases();
+        }
+
+        /// Set the default terms: the lock period applied when `add` omits one, the
+        /// dispute window and the cancel window
+        #[ink(message, selector = 0x164F2FD1)]
+        pub fn set_default_terms(&mut self,
+            lock_period: BlockNumber,
+            dispute_window: BlockNumber,
+            cancel_window: BlockNumber) -> Result<(), Error> {
+
+            // Only the owner sets the terms
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.default_lock_period = lock_period;
+            self.dispute_window = dispute_window;
+            self.cancel_window = cancel_window;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowTermsUpdated {
+                    lock_period,
+                    dispute_window,
+                    cancel_window,
+                }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the default terms (lock period, dispute window, cancel window)
+        #[ink(message, selector = 0x763D3493)]
+        pub fn get_default_terms(&self) -> (BlockNumber, BlockNumber, BlockNumber) {
+            (self.default_lock_period, self.dispute_window, self.cancel_window)
+        }
+
+        /// Create new escrow service holding the native token of the chain,
+        /// deposited through `deposit_native`, `asset_id` only labels the escrow
+        /// asset of the accounts
+        #[ink(constructor, selector = 0x4E7F4565)]
+        pub fn new_native(asset_id: u128, maximum_accounts: u16) -> Self {
+            let mut escrow = Self::new(asset_id, maximum_accounts);
+            escrow.backend = TransferBackend::Native;
+            escrow
+        }
+
+        /// Get the number of accounts that can still be added, `None` when the
+        /// maximum is 0 and the accounts are only bounded by storage
+        #[ink(message, selector = 0xCAB1A8C1)]
+        pub fn remaining_capacity(&self) -> Option<u16> {
+            if self.maximum_accounts == 0 {
+                return None;
+            }
+            Some(self.maximum_accounts.saturating_sub(self.account_count))
+        }
+
+        /// Get the backend holding the escrow asset
+        #[ink(message, selector = 0xBEDA5AF3)]
+        pub fn get_transfer_backend(&self) -> TransferBackend {
+            self.backend
+        }
+
+        /// Set the blocks before the end of a timelock `ReleaseDueSoon` is emitted
+        #[ink(message, selector = 0xAF4194F6)]
+        pub fn set_release_notice(&mut self, notice: BlockNumber) -> Result<(), Error> {
+
+            // Only the owner can change the notice period
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.release_notice = notice;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Check the next `NOTICE_SCAN_ACCOUNTS` escrow accounts and announce the
+        /// timelocks ending within `release_notice` blocks, each due block once
+        fn notify_due_releases(&mut self) {
+            if self.release_notice == 0 || self.account_count == 0 {
+                return;
+            }
+
+            let now = self.env().block_number();
+            for _ in 0..NOTICE_SCAN_ACCOUNTS.min(self.account_count) {
+                if self.notice_cursor >= self.account_count {
+                    self.notice_cursor = 0;
+                }
+                let slot = self.notice_cursor;
+                self.notice_cursor += 1;
+
+                let Some(a) = self.account_slots.get(slot).and_then(|account| self.accounts.get(account)) else {
+                    continue;
+                };
+                let due_block = a.paused_until.max(a.release_after);
+                if due_block > now
+                    && due_block - now <= self.release_notice
+                    && self.notified_due.get(a.reference) != Some(due_block) {
+                    self.announce_due(a.reference, due_block);
+                }
+            }
+        }
+
+        /// Emit `ReleaseDueSoon`, a due block within the notice period is not
+        /// announced again
+        fn announce_due(&mut self, reference: u16, due_block: BlockNumber) {
+            if due_block.saturating_sub(self.env().block_number()) <= self.release_notice {
+                self.notified_due.insert(reference, &due_block);
+            }
+            self.env().emit_event(ReleaseDueSoon {
+                reference,
+                due_block,
+            });
+        }
+
+        /// Emit the `StateCheckpoint` event and restart the operation count
+        fn emit_checkpoint(&mut self) {
+            self.checkpoint_counter = self.checkpoint_counter.wrapping_add(1);
+            self.operations_since_checkpoint = 0;
+
+            self.env().emit_event(StateCheckpoint {
+                checkpoint: self.checkpoint_counter,
+                block_number: self.env().block_number(),
+                asset_id: self.asset_id,
+                owner: self.owner,
+                manager: self.manager,
+                maximum_accounts: self.maximum_accounts,
+                status: self.status,
+                close_policy: self.close_policy,
+                bond_required: self.bond_required,
+                bond: self.bond,
+                reference_counter: self.reference_counter,
+                account_count: self.account_count,
+                total_balance: self.total_balance,
+                queued_amount: self.queued_amount,
+                dust_pot: self.dust_pot,
+                accounts_root: self.accounts_root(),
+            });
+        }
+
+        /// Close the escrow service. A legacy message, a rejected close returns `Ok`
+        /// and reports the error through the event only.
+        #[ink(message, selector = 0x3C922166)]
+        pub fn close(&mut self) -> Result<(), Error> {
+
+            // Closing the can only be done by a pauser
+            let caller = self.env().caller();
+            if !self.has_role(Role::Pauser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Ok(());
+            } 
+
+            // A close must not strand funds, the accounts are settled or refunded first
+            if self.close_requires_empty && self.total_balance > 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsNotEmpty),
+                });
+                return Ok(());
+            }
+
+            // This will close the Escrow
+            self.status = 1;
+            self.closed_at = self.env().block_number();
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowCloseSuccess),
+            });
+            self.env().emit_event(Closed { operator: caller });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Open the escrow service
+        #[ink(message, selector = 0x8A1B6A4C)]
+        pub fn open(&mut self) -> Result<(), Error> {
+
+            // Opening the can only be done by a pauser
+            let caller = self.env().caller();
+            if !self.has_role(Role::Pauser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            } 
+
+            // The manager bond must be posted before the escrow can open
+            if self.bond < self.bond_required {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowBondInsufficient),
+                });
+                return Err(Error::EscrowBondInsufficient);
+            }
+
+            // This will open the Escrow
+            self.status = 0;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowOpenSuccess),
+            });
+            self.env().emit_event(Opened { operator: caller });
+
+            Ok(())
+        }   
+        
+        /// Add escrow account, done only by the manager once the transfer of the asset
+        /// us verified through the tx-hash, the tx-hash is stored and emitted so anyone
+        /// can confirm the funding transaction. A legacy message, a rejected add
+        /// returns `Ok` and reports the error through the event only.
+        #[ink(message, selector = 0x4B050EA9)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn add(&mut self,
+            reference: u16,
+            account: AccountId,
+            asset_id: u128,
+            amount: u128,
+            recipient: AccountId,
+            tx_hash: Hash,
+            template: Option<u16>,
+            lock_period: Option<BlockNumber>,
+            key: Option<Hash>) -> Result<Option<Receipt>, Error> {
+            Ok(self
+                .add_escrow_account(reference, account, asset_id, amount, recipient, tx_hash, template, lock_period, key)
+                .unwrap_or(None))
+        }
+
+        /// Add an escrow account, the error of a rejected add is emitted and returned.
+        /// Returns the receipt of a keyed add.
+        #[allow(clippy::too_many_arguments)]
+        pub(crate) fn add_escrow_account(&mut self,
+            reference: u16,
+            account: AccountId,
+            asset_id: u128,
+            amount: u128,
+            recipient: AccountId,
+            tx_hash: Hash,
+            template: Option<u16>,
+            lock_period: Option<BlockNumber>,
+            key: Option<Hash>) -> Result<Option<Receipt>, Error> {
+
+            // Adding escrow account can only be done by an adder once the transfer of the
+            // asset is verified through the tx-hash.
+            let caller = self.env().caller();
+            if !self.has_role(Role::Adder, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            } 
+
+            // A replayed idempotency key is a no-op
+            if let Some(receipt) = self.replayed(caller, KeyedOperation::Add, key) {
+                return Ok(Some(receipt));
+            }
+
+            // Check if the escrow accepts new accounts
+            if self.adds_blocked() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                });
+                return Err(Error::EscrowIsClose);
+            }
+
+            // Operators can halt new funds on their own
+            if self.is_paused(PAUSE_ADD) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowOperationPaused),
+                });
+                return Err(Error::EscrowOperationPaused);
+            }
+
+            // Under the allow-list only listed depositors enter the escrow
+            if !self.depositor_allowed(account) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowDepositorNotAllowed),
+                });
+                return Err(Error::EscrowDepositorNotAllowed);
+            }
+
+            if self.account_blocked(recipient) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::RecipientBlocked),
+                });
+                return Err(Error::RecipientBlocked);
+            }
+
+            // Check if there is a duplicate escrow account, references are unique as
+            // they index the accounts and serve as position NFT token ids
+            if self.accounts.contains(account)
+                || self.references.contains(reference)
+                || self.nft_escrows.contains(reference) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDuplicate),
+                });
+                return Err(Error::EscrowAccountDuplicate);
+            }
+
+            // Add the escrow account
+            if self.at_capacity() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountMax),
+                });
+                return Err(Error::EscrowAccountMax);
+            }
+
+            // Check if the payout template exists
+            if let Some(id) = template {
+                if !self.templates.contains(id) {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowTemplateNotFound),
+                    });
+                    return Err(Error::EscrowTemplateNotFound);
+                }
+            }
+
+            // The add must confirm the intent registered by the depositor
+            if self.require_intents {
+                let Some(intent) = self.intents.get(account) else {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowIntentMismatch),
+                    });
+                    return Err(Error::EscrowIntentMismatch);
+                };
+                if self.intent_expired(&intent) {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowIntentExpired),
+                    });
+                    return Err(Error::EscrowIntentExpired);
+                }
+                if intent.reference != reference || intent.amount != amount || intent.recipient != recipient {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowIntentMismatch),
+                    });
+                    return Err(Error::EscrowIntentMismatch);
+                }
+                self.remove_intent(account);
+            }
+
+            // The account can only be released once the lock period has passed, the
+            // default terms apply when the add omits it
+            let lock_period = lock_period.unwrap_or(self.default_lock_period);
+            let release_after = if lock_period == 0 {
+                0
+            } else {
+                self.env().block_number().saturating_add(lock_period)
+            };
+
+            let new_account = Account {
+                reference,
+                account,
+                asset_id,
+                balance: amount,
+                recipient: Recipient::Local(recipient),
+                status: if self.recipient_acceptance { 3 } else { 1 }, // 1 = Liquid, 3 = Pending
+                confirmed: !self.recipient_confirmation,
+                tags: Vec::new(),
+                template,
+                nft: self.nft_positions,
+                added_at: self.env().block_number(),
+                tx_hash,
+                correlation_id: None,
+                paused_until: 0,
+                release_after,
+                expires_at: 0,
+            };
+            
+            self.insert_account(&new_account);
+            if release_after != 0 {
+                self.announce_due(reference, release_after);
+            }
+            self.record_history(reference, Activity::Added);
+
+            let mut metrics = self.manager_metrics.get(caller).unwrap_or_default();
+            metrics.accounts_added = metrics.accounts_added.saturating_add(1);
+            self.manager_metrics.insert(caller, &metrics);
+
+            self.env().emit_event(FundingCommitted {
+                account,
+                reference,
+                amount,
+                tx_hash,
+            });
+
+            // Mint the position NFT to the depositor
+            if self.nft_positions {
+                self.env().emit_event(Transfer {
+                    from: None,
+                    to: Some(account),
+                    id: Id::U16(reference),
+                });
+            }
+
+            let receipt = self.record_receipt(caller, KeyedOperation::Add, key, Success::EscrowAccountAdded { reference }, reference, account, amount);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowAccountAdded { reference }),
+            });
+
+            self.assert_invariants();
+
+            Ok(receipt)
+        }
+
+        /// Register the terms of a deposit before funding it, the manager then confirms
+        /// the intent through `add`
+        #[ink(message, selector = 0x936321D3)]
+        pub fn register_intent(&mut self,
+            reference: u16,
+            amount: u128,
+            recipient: AccountId) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+
+            // Check if the escrow accepts new accounts
+            if self.adds_blocked() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                });
+                return Err(Error::EscrowIsClose);
+            }
+
+            // Operators can halt new funds on their own
+            if self.is_paused(PAUSE_ADD) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowOperationPaused),
+                });
+                return Err(Error::EscrowOperationPaused);
+            }
+
+            // Under the allow-list only listed depositors enter the escrow
+            if !self.depositor_allowed(caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowDepositorNotAllowed),
+                });
+                return Err(Error::EscrowDepositorNotAllowed);
+            }
+
+            // The queue stays bounded, the purge makes room
+            if self.intent_tail.wrapping_sub(self.intent_head) >= MAXIMUM_INTENTS {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIntentMax),
+                });
+                return Err(Error::EscrowIntentMax);
+            }
+
+            // A replaced intent leaves a stale queue entry the purge skips
+            let registered_at = self.env().block_number();
+            self.intents.insert(caller, &Intent {
+                reference,
+                amount,
+                recipient,
+                registered_at,
+            });
+            self.intent_queue.insert(self.intent_tail, &(caller, registered_at));
+            self.intent_tail = self.intent_tail.wrapping_add(1);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowIntentRegistered { reference }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Cancel the deposit intent of the caller
+        #[ink(message, selector = 0xF55F21F9)]
+        pub fn cancel_intent(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            if !self.remove_intent(caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIntentNotFound),
+                });
+                return Err(Error::EscrowIntentNotFound);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowIntentCancelled),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the deposit intent of a depositor
+        #[ink(message, selector = 0x8CFDE611)]
+        pub fn get_intent(&self, account: AccountId) -> Option<Intent> {
+            self.intents.get(account)
+        }
+
+        /// Remove expired deposit intents from the head of the intent queue, checking
+        /// up to `limit` entries. Callable by anyone.
+        #[ink(message, selector = 0x8086685E)]
+        pub fn purge_expired_intents(&mut self, limit: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut checked = 0u32;
+            let mut purged = 0u32;
+            while checked < limit && self.intent_head != self.intent_tail {
+                let head = self.intent_head;
+                if let Some((account, registered_at)) = self.intent_queue.get(head) {
+                    // Entries of cancelled, confirmed or replaced intents are stale
+                    match self.intents.get(account) {
+                        Some(intent) if intent.registered_at == registered_at => {
+                            // The queue is in registration order, the later intents
+                            // have not expired either
+                            if !self.intent_expired(&intent) {
+                                break;
+                            }
+                            self.intents.remove(account);
+                            purged += 1;
+                        }
+                        _ => {}
+                    }
+                    self.intent_queue.remove(head);
+                }
+                self.intent_head = head.wrapping_add(1);
+                checked += 1;
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowIntentsPurged { purged }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Check if a deposit intent is past its time to live
+        fn intent_expired(&self, intent: &Intent) -> bool {
+            self.env().block_number() > intent.registered_at.saturating_add(self.intent_ttl)
+        }
+
+        /// Remove the deposit intent of a depositor, returns false if there was none.
+        /// Its queue entry turns stale and is dropped by the purge.
+        fn remove_intent(&mut self, account: AccountId) -> bool {
+            self.intents.take(account).is_some()
+        }
+
+        /// Require `add` to confirm a matching intent registered by the depositor, and
+        /// set the blocks an intent stays valid
+        #[ink(message, selector = 0xB72043D5)]
+        pub fn set_require_intents(&mut self, required: bool, ttl: BlockNumber) -> Result<(), Error> {
+
+            // Only the owner can change the add flow
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.require_intents = required;
+            self.intent_ttl = ttl;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Add escrow account with a reference assigned by the contract
+        #[ink(message, selector = 0xAF9C6C61)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn add_auto(&mut self,
+            account: AccountId,
+            asset_id: u128,
+            amount: u128,
+            recipient: AccountId,
+            tx_hash: Hash,
+            template: Option<u16>,
+            lock_period: Option<BlockNumber>,
+            key: Option<Hash>) -> Result<Option<Receipt>, Error> {
+
+            // Only an adder can consume references
+            let caller = self.env().caller();
+            if !self.has_role(Role::Adder, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            // A replayed idempotency key must not consume a reference
+            if let Some(receipt) = self.replayed(caller, KeyedOperation::Add, key) {
+                return Ok(Some(receipt));
+            }
+
+            let reference = self.next_reference();
+            self.reference_counter = reference.wrapping_add(1);
+
+            self.add_escrow_account(reference, account, asset_id, amount, recipient, tx_hash, template, lock_period, key)
+        }
+
+        /// Get the next reference to be assigned by `add_auto`, references already
+        /// used by escrow accounts or NFT escrows are skipped
+        #[ink(message, selector = 0x7BE23A5F)]
+        pub fn next_reference(&self) -> u16 {
+            let mut reference = self.reference_counter;
+            while self.references.contains(reference) || self.nft_escrows.contains(reference) {
+                reference = reference.wrapping_add(1);
+            }
+            reference
+        }
+
+        /// Add an NFT escrow, can only be done by the manager once the transfer of the
+        /// `pallet_nfts` item to the escrow is verified. The reference must not be
+        /// used by a fungible escrow account.
+        #[ink(message, selector = 0x1306E4FE)]
+        pub fn add_nft(&mut self,
+            reference: u16,
+            account: AccountId,
+            collection: u32,
+            item: u32,
+            recipient: AccountId) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            // Check if the escrow accepts new accounts
+            if self.adds_blocked() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                });
+                return Err(Error::EscrowIsClose);
+            }
+
+            // Operators can halt new funds on their own
+            if self.is_paused(PAUSE_ADD) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowOperationPaused),
+                });
+                return Err(Error::EscrowOperationPaused);
+            }
+
+            // Under the allow-list only listed depositors enter the escrow
+            if !self.depositor_allowed(account) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowDepositorNotAllowed),
+                });
+                return Err(Error::EscrowDepositorNotAllowed);
+            }
+
+            if self.references.contains(reference) || self.nft_escrows.contains(reference) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDuplicate),
+                });
+                return Err(Error::EscrowAccountDuplicate);
+            }
+
+            self.nft_escrows.insert(reference, &NftEscrow {
+                reference,
+                account,
+                collection,
+                item,
+                recipient,
+                added_at: self.env().block_number(),
+            });
+            self.nft_count += 1;
+            self.record_history(reference, Activity::Added);
+
+            let operation = Success::EscrowNftAdded { reference, collection, item };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(None, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Release an NFT escrow to its recipient, by its depositor
+        #[ink(message, selector = 0xC54804A7)]
+        pub fn release_nft(&mut self, reference: u16) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            let escrow = match self.nft_escrows.get(reference) {
+                None => Err(Error::EscrowAccountNotFound),
+                Some(escrow) if escrow.account != caller => Err(Error::BadOrigin),
+                Some(_) if self.releases_blocked() => Err(Error::EscrowIsClose),
+                Some(_) if self.is_paused(PAUSE_RELEASE) => Err(Error::EscrowOperationPaused),
+                Some(escrow) => Ok(escrow),
+            };
+            let escrow = match escrow {
+                Ok(escrow) => escrow,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            self
+                .dispatch(&RuntimeCall::Nfts(NftsCall::Transfer {
+                    collection: escrow.collection,
+                    item: escrow.item,
+                    dest: escrow.recipient.into(),
+                }))
+                .map_err(|_| RuntimeError::CallRuntimeFailed)?;
+
+            self.nft_escrows.remove(reference);
+            self.nft_count -= 1;
+            self.record_history(reference, Activity::Released);
+
+            let operation = Success::EscrowNftReleased {
+                reference,
+                collection: escrow.collection,
+                item: escrow.item,
+            };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(None, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get an NFT escrow
+        #[ink(message, selector = 0x26443695)]
+        pub fn get_nft(&self, reference: u16) -> Option<NftEscrow> {
+            self.nft_escrows.get(reference)
+        }
+
+        /// Open an escrow account as the depositor, callable by anyone. The caller must
+        /// first approve the escrow contract to spend the amount through
+        /// `pallet_assets::approve_transfer`, the funds are pulled and the account
+        /// created in the same call, so no manager has to verify a funding transaction.
+        /// A repeat deposit to the caller's own reference tops up that account.
+        #[ink(message, selector = 0x2D10C9BD)]
+        pub fn deposit(&mut self,
+            reference: u16,
+            amount: u128,
+            recipient: AccountId) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+
+            let top_up = match self.check_deposit(reference) {
+                Ok(top_up) => top_up,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            // Pull the funds from the depositor into the escrow
+            self.pull(self.asset_id, caller, amount)?;
+
+            if top_up {
+                return self.top_up_deposit(caller, amount).map_err(Into::into);
+            }
+
+            self.open_deposit(caller, reference, amount, recipient);
+
+            Ok(())
+        }
+
+        /// Open an escrow account as the depositor with the transferred native value,
+        /// the escrow must hold the native token
+        #[ink(message, payable, selector = 0x9B408047)]
+        pub fn deposit_native(&mut self,
+            reference: u16,
+            recipient: AccountId) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+            let amount = self.env().transferred_value();
+
+            let check = if self.backend != TransferBackend::Native {
+                Err(Error::EscrowWrongBackend)
+            } else if amount == 0 {
+                Err(Error::EscrowAmountInvalid)
+            } else {
+                self.check_deposit(reference)
+            };
+            let top_up = match check {
+                Ok(top_up) => top_up,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error);
+                }
+            };
+
+            if top_up {
+                return self.top_up_deposit(caller, amount);
+            }
+
+            self.open_deposit(caller, reference, amount, recipient);
+
+            Ok(())
+        }
+
+        /// Check if the caller can open an escrow account by depositing, returns true
+        /// when the deposit tops up the caller's own account of the reference
+        fn check_deposit(&self, reference: u16) -> Result<bool, Error> {
+            let caller = self.env().caller();
+
+            // Depositing is blocked the same way as adding
+            if self.adds_blocked() {
+                return Err(Error::EscrowIsClose);
+            }
+            if self.is_paused(PAUSE_ADD) {
+                return Err(Error::EscrowOperationPaused);
+            }
+            if !self.depositor_allowed(caller) {
+                return Err(Error::EscrowDepositorNotAllowed);
+            }
+
+            // A repeat deposit to the caller's own reference tops it up
+            if self.references.get(reference) == Some(caller) {
+                return Ok(true);
+            }
+
+            // One escrow per account, references are unique
+            if self.accounts.contains(caller)
+                || self.references.contains(reference)
+                || self.nft_escrows.contains(reference) {
+                return Err(Error::EscrowAccountDuplicate);
+            }
+
+            if self.at_capacity() {
+                return Err(Error::EscrowAccountMax);
+            }
+
+            Ok(false)
+        }
+
+        /// Top up the escrow account of a depositor whose repeat deposit arrived
+        fn top_up_deposit(&mut self, caller: AccountId, amount: u128) -> Result<(), Error> {
+            let result = self.top_up_account(caller, amount);
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: match result.clone() {
+                    Ok(operation) => EscrowStatus::EmitSuccess(operation),
+                    Err(error) => EscrowStatus::EmitError(error),
+                },
+            });
+
+            self.assert_invariants();
+
+            result.map(|_| ())
+        }
+
+        /// Create the escrow account of a depositor whose funds arrived
+        fn open_deposit(&mut self,
+            caller: AccountId,
+            reference: u16,
+            amount: u128,
+            recipient: AccountId) {
+
+            self.insert_account(&Account {
+                reference,
+                account: caller,
+                asset_id: self.asset_id,
+                balance: amount,
+                recipient: Recipient::Local(recipient),
+                status: 1, // 1 = Liquid
+                confirmed: !self.recipient_confirmation,
+                tags: Vec::new(),
+                template: None,
+                nft: self.nft_positions,
+                added_at: self.env().block_number(),
+                tx_hash: Hash::default(),
+                correlation_id: None,
+                paused_until: 0,
+                release_after: 0,
+                expires_at: 0,
+            });
+            self.record_history(reference, Activity::Deposited);
+
+            // Mint the position NFT to the depositor
+            if self.nft_positions {
+                self.env().emit_event(Transfer {
+                    from: None,
+                    to: Some(caller),
+                    id: Id::U16(reference),
+                });
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowAccountAdded { reference }),
+            });
+
+            self.assert_invariants();
+        }
+
+        /// Fund an existing escrow account, callable by anyone. The caller must first
+        /// approve the escrow contract to spend the amount through
+        /// `pallet_assets::approve_transfer`
+        #[ink(message, selector = 0x4AAFA343)]
+        pub fn fund(&mut self, reference: u16, amount: u128) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+
+            // Funding is blocked the same way as adding
+            if self.adds_blocked() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                });
+                return Err(Error::EscrowIsClose.into());
+            }
+
+            // Operators can halt new funds on their own
+            if self.is_paused(PAUSE_ADD) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowOperationPaused),
+                });
+                return Err(Error::EscrowOperationPaused.into());
+            }
+
+            // A zero contribution would only take a contributor slot
+            if amount == 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAmountInvalid),
+                });
+                return Err(Error::EscrowAmountInvalid.into());
+            }
+
+            let Some(mut a) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // Record the contribution, merging repeated contributions of the same address
+            let mut contributions = self.contributions.get(reference).unwrap_or_default();
+            match contributions.iter_mut().find(|c| c.contributor == caller) {
+                Some(c) => c.amount = c.amount.saturating_add(amount),
+                None => {
+                    if contributions.len() >= MAXIMUM_CONTRIBUTORS {
+                        self.env().emit_event(EscrowEvent {
+                            operator: caller,
+                            status: EscrowStatus::EmitError(Error::EscrowContributorMax),
+                        });
+                        return Err(Error::EscrowContributorMax.into());
+                    }
+                    contributions.push(Contribution { contributor: caller, amount });
+                }
+            }
+
+            // Pull the funds from the contributor into the escrow
+            self.pull(a.asset_id, caller, amount)?;
+
+            a.balance = a.balance.saturating_add(amount);
+            self.save_account(&a);
+            self.credit(a.asset_id, amount);
+            self.contributions.insert(reference, &contributions);
+            self.record_history(reference, Activity::Funded);
+
+            let operation = Success::EscrowAccountFunded { reference, amount };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the contributor breakdown of an escrow account, the depositor comes first
+        /// with the balance not covered by third-party contributions
+        #[ink(message, selector = 0xA55D54A1)]
+        pub fn get_contributions(&self, reference: u16) -> Vec<Contribution> {
+            let Some(a) = self.account_by_reference(reference) else {
+                return Vec::new();
+            };
+
+            let funded = self.contributions.get(reference).unwrap_or_default();
+            let funded_total = funded
+                .iter()
+                .fold(0u128, |total, c| total.saturating_add(c.amount));
+
+            let mut contributions = ink::prelude::vec![Contribution {
+                contributor: a.account,
+                amount: a.balance.saturating_sub(funded_total),
+            }];
+            contributions.extend(funded);
+            contributions
+        }
+
+        /// Top up the balance of an existing escrow account, returns the receipt of a
+        /// keyed top up
+        #[ink(message, selector = 0x29B2765C)]
+        pub fn top_up(&mut self, account: AccountId, amount: u128, key: Option<Hash>) -> Result<Option<Receipt>, Error> {
+
+            // Only the manager can top up
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            // A replayed idempotency key is a no-op
+            if let Some(receipt) = self.replayed(caller, KeyedOperation::TopUp, key) {
+                return Ok(Some(receipt));
+            }
+
+            let result = self.top_up_account(account, amount);
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: match result.clone() {
+                    Ok(operation) => EscrowStatus::EmitSuccess(operation),
+                    Err(error) => EscrowStatus::EmitError(error),
+                },
+            });
+            let operation = result?;
+            let reference = self.accounts.get(account).map(|a| a.reference).unwrap_or_default();
+            let receipt = self.record_receipt(caller, KeyedOperation::TopUp, key, operation, reference, account, amount);
+
+            self.assert_invariants();
+
+            Ok(receipt)
+        }
+
+        /// Top up the balances of a batch of escrow accounts, each entry emits its own
+        /// event and a failed entry does not stop the batch. The batch stops before
+        /// running out of gas and returns the cursor of the first unprocessed entry.
+        #[ink(message, selector = 0x527A6441)]
+        pub fn top_up_batch(&mut self, entries: Vec<(AccountId, u128)>) -> Result<BatchOutcome, Error> {
+
+            // Only the manager can top up
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let total = entries.len() as u32;
+            let mut outcome = BatchOutcome::default();
+            for (n, (account, amount)) in entries.into_iter().enumerate() {
+                if self.batch_exhausted(caller, n as u32, total) {
+                    outcome.cursor = Some(n as u32);
+                    break;
+                }
+                let result = self.top_up_account(account, amount);
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: match result.clone() {
+                        Ok(operation) => EscrowStatus::EmitSuccess(operation),
+                        Err(error) => EscrowStatus::EmitError(error),
+                    },
+                });
+                outcome.results.push(BatchResult { account, error: result.err() });
+            }
+
+            self.assert_invariants();
+
+            Ok(outcome)
+        }
+
+        /// Freeze an escrow account, frozen accounts cannot be released
+        #[ink(message, selector = 0xB5FB2DE4)]
+        pub fn freeze(&mut self, account: AccountId) -> Result<(), Error> {
+            self.set_frozen(account, true)
+        }
+
+        /// Unfreeze an escrow account
+        #[ink(message, selector = 0x40715F40)]
+        pub fn unfreeze(&mut self, account: AccountId) -> Result<(), Error> {
+            self.set_frozen(account, false)
+        }
+
+        /// Freeze a batch of escrow accounts, each entry emits its own event and a
+        /// failed entry does not stop the batch. The batch stops before running out of
+        /// gas and returns the cursor of the first unprocessed entry.
+        #[ink(message, selector = 0x7D2F68B9)]
+        pub fn freeze_batch(&mut self, accounts: Vec<AccountId>) -> Result<BatchOutcome, Error> {
+
+            // Only the manager can freeze
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let total = accounts.len() as u32;
+            let mut outcome = BatchOutcome::default();
+            for (n, account) in accounts.into_iter().enumerate() {
+                if self.batch_exhausted(caller, n as u32, total) {
+                    outcome.cursor = Some(n as u32);
+                    break;
+                }
+                let result = self.freeze_account(account, true);
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: match result.clone() {
+                        Ok(operation) => EscrowStatus::EmitSuccess(operation),
+                        Err(error) => EscrowStatus::EmitError(error),
+                    },
+                });
+                outcome.results.push(BatchResult { account, error: result.err() });
+            }
+
+            self.assert_invariants();
+
+            Ok(outcome)
+        }
+
+        /// Check if a batch must stop before the entry at `cursor` to keep the gas
+        /// reserve, emitting `BatchPartiallyProcessed` if so
+        fn batch_exhausted(&self, operator: AccountId, cursor: u32, total: u32) -> bool {
+            if self.gas_left() >= BATCH_GAS_RESERVE {
+                return false;
+            }
+
+            self.env().emit_event(BatchPartiallyProcessed {
+                operator,
+                cursor,
+                total,
+            });
+
+            true
+        }
+
+        /// Gas left in the message
+        #[cfg(not(test))]
+        fn gas_left(&self) -> u64 {
+            self.env().gas_left()
+        }
+
+        /// Gas left in the message, the off-chain environment does not meter gas so
+        /// the unit tests feed it through `tests::GAS_LEFT`
+        #[cfg(test)]
+        fn gas_left(&self) -> u64 {
+            tests::GAS_LEFT.with(|gas| gas.get())
+        }
+
+        /// Freeze or unfreeze an escrow account by the manager
+        fn set_frozen(&mut self, account: AccountId, frozen: bool) -> Result<(), Error> {
+
+            // Only the manager can freeze
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let result = self.freeze_account(account, frozen);
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: match result.clone() {
+                    Ok(operation) => EscrowStatus::EmitSuccess(operation),
+                    Err(error) => EscrowStatus::EmitError(error),
+                },
+            });
+
+            self.assert_invariants();
+
+            result.map(|_| ())
+        }
+
+        /// Top up one escrow account, shared by the single and batch messages
+        fn top_up_account(&mut self, account: AccountId, amount: u128) -> Result<Success, Error> {
+
+            // Topping up is blocked the same way as adding
+            if self.adds_blocked() {
+                return Err(Error::EscrowIsClose);
+            }
+            if self.is_paused(PAUSE_ADD) {
+                return Err(Error::EscrowOperationPaused);
+            }
+
+            let mut a = self.accounts.get(account).ok_or(Error::EscrowAccountNotFound)?;
+
+            a.balance = a.balance.saturating_add(amount);
+            self.save_account(&a);
+            self.credit(a.asset_id, amount);
+            let reference = a.reference;
+            self.record_history(reference, Activity::ToppedUp);
+
+            let operation = Success::EscrowAccountToppedUp { reference, amount };
+            self.emit_account_event(a.correlation_id, reference, operation.clone());
+
+            Ok(operation)
+        }
+
+        /// Freeze or unfreeze one escrow account, shared by the single and batch
+        /// messages
+        fn freeze_account(&mut self, account: AccountId, frozen: bool) -> Result<Success, Error> {
+            let mut a = self.accounts.get(account).ok_or(Error::EscrowAccountNotFound)?;
+
+            // A dispute keeps the account frozen until the arbiter resolves it
+            if a.status == 2 {
+                return Err(Error::EscrowAccountDisputed);
+            }
+
+            // Only the recipient acceptance activates a pending account
+            if a.status == 3 {
+                return Err(Error::EscrowAccountPending);
+            }
+
+            // 0 = Frozen, 1 = Liquid
+            a.status = if frozen { 0 } else { 1 };
+            self.save_account(&a);
+            let reference = a.reference;
+
+            let operation = if frozen {
+                self.record_history(reference, Activity::Frozen);
+                Success::EscrowAccountFrozen { reference }
+            } else {
+                self.record_history(reference, Activity::Unfrozen);
+                Success::EscrowAccountUnfrozen { reference }
+            };
+            self.emit_account_event(a.correlation_id, reference, operation.clone());
+
+            Ok(operation)
+        }
+
+        /// Transfer an escrow account with its funds and terms to another escrow contract
+        /// implementing `EscrowReceiver`
+        #[ink(message, selector = 0xB43805ED)]
+        pub fn transfer_to_escrow(&mut self,
+            reference: u16,
+            other_escrow: AccountId) -> Result<(), ContractError> {
+
+            // Migrating positions can only be done by the manager
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if the escrow allows releases
+            if self.releases_blocked() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                });
+                return Err(Error::EscrowIsClose.into());
+            }
+
+            // Releases are paused until the owner acknowledges a detected insolvency
+            if self.insolvency_paused {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowInsolvencyPaused),
+                });
+                return Err(Error::EscrowInsolvencyPaused.into());
+            }
+
+            let Some(account) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // A disputed account stays until the arbiter settles it
+            if account.status == 2 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDisputed),
+                });
+                return Err(Error::EscrowAccountDisputed.into());
+            }
+
+            // The receiver interface only carries recipients on this chain
+            let Some(recipient) = account.recipient.local_account() else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowRecipientUnsupported),
+                });
+                return Err(Error::EscrowRecipientUnsupported.into());
+            };
+
+            // Let the other escrow pull the funds
+            self.approve_pull(account.asset_id, other_escrow, account.balance)?;
+
+            // Any failure on the other escrow reverts the whole transfer
+            let mut receiver: ink::contract_ref!(EscrowReceiver) = other_escrow.into();
+            receiver.receive_position(
+                account.asset_id,
+                account.reference,
+                account.account,
+                account.balance,
+                recipient,
+            )?;
+
+            self.remove_account(&account, Activity::TransferredOut);
+
+            let operation = Success::EscrowAccountTransferred {
+                reference: account.reference,
+                amount: account.balance,
+            };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(account.correlation_id, account.reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Allow or disallow another escrow contract to transfer positions into this
+        /// escrow
+        #[ink(message, selector = 0xE438DDAE)]
+        pub fn set_accepted_escrow(&mut self,
+            escrow: AccountId,
+            accepted: bool) -> Result<(), Error> {
+
+            // Only the manager decides which escrows can transfer in
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if accepted {
+                self.accepted_escrows.insert(escrow, &());
+            } else {
+                self.accepted_escrows.remove(escrow);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Released the escrow account balance to the recipient. A legacy message, a
+        /// rejected release returns `Ok` and reports the error through the event only,
+        /// a failed transfer returns the runtime error.
+        #[ink(message, selector = 0x3F2BE152)]
+        pub fn release(&mut self) -> Result<(), ContractError> {
+            match self.release_attested(self.env().caller(), None) {
+                Err(ContractError::Internal(_)) => Ok(()),
+                result => result,
+            }
+        }
+
+        /// Release the escrow account balance to the recipient, binding the hash of a
+        /// delivery or acceptance document into the release receipt and event
+        #[ink(message, selector = 0x12BE1888)]
+        pub fn release_with_attestation(&mut self, doc_hash: Hash) -> Result<(), ContractError> {
+
+            // A document attests a single release
+            if self.attestations.contains(doc_hash) {
+                self.env().emit_event(EscrowEvent {
+                    operator: self.env().caller(),
+                    status: EscrowStatus::EmitError(Error::EscrowAttestationUsed),
+                });
+                return Err(Error::EscrowAttestationUsed.into());
+            }
+
+            self.release_attested(self.env().caller(), Some(doc_hash))
+        }
+
+        /// Release the escrow account of the caller by its reference, the dApp key of
+        /// the account
+        #[ink(message, selector = 0xB388F987)]
+        pub fn release_by_reference(&mut self, reference: u16) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            // The reference must be the account of the caller
+            if self.references.get(reference) != Some(caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            }
+
+            self.release_attested(caller, None)
+        }
+
+        /// Release the escrow account of a depositor on its signed authorization, so
+        /// a relayer pays the gas. The depositor signs `get_release_message(account,
+        /// nonce)` with its ECDSA key, the account id being the Blake2x256 hash of the
+        /// compressed public key, and each signature is used once.
+        #[ink(message, selector = 0x9189E0A9)]
+        pub fn release_with_sig(&mut self,
+            account: AccountId,
+            nonce: u64,
+            signature: [u8; 65]) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+
+            // Signatures are single use, the nonce must be the next one of the account
+            if nonce != self.release_nonces.get(account).unwrap_or(0) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowNonceInvalid),
+                });
+                return Err(Error::EscrowNonceInvalid.into());
+            }
+
+            // Recover the signer of the release message
+            let message: [u8; 32] = self.get_release_message(account, nonce).into();
+            let signer = self.env()
+                .ecdsa_recover(&signature, &message)
+                .ok()
+                .map(|public_key| AccountId::from(self.env().hash_bytes::<Blake2x256>(&public_key)));
+            if signer != Some(account) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowSignatureInvalid),
+                });
+                return Err(Error::EscrowSignatureInvalid.into());
+            }
+
+            self.release_nonces.insert(account, &(nonce + 1));
+
+            self.release_attested(account, None)
+        }
+
+        /// Get the next release signature nonce of a depositor
+        #[ink(message, selector = 0xEDD4EE65)]
+        pub fn get_release_nonce(&self, account: AccountId) -> u64 {
+            self.release_nonces.get(account).unwrap_or(0)
+        }
+
+        /// Get the message a depositor signs to authorize `release_with_sig`, bound to
+        /// this escrow, the depositor and the nonce
+        #[ink(message, selector = 0x71FD4046)]
+        pub fn get_release_message(&self, account: AccountId, nonce: u64) -> Hash {
+            self.env().hash_encoded::<Blake2x256, _>(&(b"release", self.env().account_id(), account, nonce)).into()
+        }
+
+        /// Release the escrow account of a depositor, with an optional document hash
+        fn release_attested(&mut self, depositor: AccountId, doc_hash: Option<Hash>) -> Result<(), ContractError> {
+
+            // The caller is the depositor, or a relayer of its signed release
+            let caller = self.env().caller();
+
+            // Check if the escrow account of the depositor can be released
+            let account = match self.check_release(depositor) {
+                Ok(account) => account,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            // Transfer the protocol fee to the collector, the rest to the recipient
+            // or the template shares
+            let amount = self.vested_amount(&account);
+            let fee = self.charge_fee(account.asset_id, amount, account.reference)?;
+            self.payout(&account, amount - fee)?;
+
+            // Remove escrow account (gas efficient), a vesting account keeps its
+            // unvested remainder
+            let reference = account.reference;
+            let correlation_id = account.correlation_id;
+            let operation = if amount < account.balance {
+                self.release_part(account, amount, fee, Activity::Released)
+            } else {
+                self.remove_account(&account, Activity::Released);
+                Success::EscrowAccountReleased { reference, amount, fee }
+            };
+
+            if let Some(doc_hash) = doc_hash {
+                self.attestations.insert(doc_hash, &Receipt {
+                    operation: operation.clone(),
+                    reference,
+                    account: depositor,
+                    amount,
+                    block_number: self.env().block_number(),
+                });
+                self.env().emit_event(ReleaseAttested {
+                    account: depositor,
+                    reference,
+                    amount,
+                    doc_hash,
+                });
+            }
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Release part of the escrow account balance of the caller to the recipient,
+        /// or split it among the template shares. The account stays with the
+        /// remainder and is removed once it reaches zero.
+        #[ink(message, selector = 0xE50252E1)]
+        pub fn release_partial(&mut self, amount: u128) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            // Check if the escrow account of the caller can be released
+            let account = match self.check_release(caller) {
+                Ok(account) if amount == 0 || amount > account.balance => Err(Error::EscrowAmountInvalid),
+                Ok(account) if amount > self.vested_amount(&account) => Err(Error::EscrowNotVested),
+                result => result,
+            };
+            let account = match account {
+                Ok(account) => account,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            // Transfer the protocol fee on the part to the collector, the rest to the
+            // recipient or the template shares
+            let fee = self.charge_fee(account.asset_id, amount, account.reference)?;
+            self.payout(&account, amount - fee)?;
+
+            let operation = self.release_part(account.clone(), amount, fee, Activity::Released);
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(account.correlation_id, account.reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Release part of an escrow account as a spender the depositor approved,
+        /// with the same checks as `release_partial`. The amount is taken off the
+        /// allowance of the caller.
+        #[ink(message, selector = 0xC8DB3735)]
+        pub fn release_from(&mut self, account: AccountId, amount: u128) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            // Check if the escrow account can be released and the caller is allowed
+            let allowance = self.allowance_of(account, caller);
+            let release = match self.check_release(account) {
+                Ok(a) if amount == 0 || amount > a.balance => Err(Error::EscrowAmountInvalid),
+                Ok(a) if amount > self.vested_amount(&a) => Err(Error::EscrowNotVested),
+                Ok(_) if amount > allowance => Err(Error::EscrowAllowanceExceeded),
+                result => result,
+            };
+            let release = match release {
+                Ok(a) => a,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            let remaining = allowance - amount;
+            if remaining == 0 {
+                self.spender_allowances.remove((account, caller));
+            } else {
+                self.spender_allowances.insert((account, caller), &(release.reference, remaining));
+            }
+
+            // Transfer the protocol fee on the part to the collector, the rest to the
+            // recipient or the template shares
+            let fee = self.charge_fee(release.asset_id, amount, release.reference)?;
+            self.payout(&release, amount - fee)?;
+
+            let operation = self.release_part(release.clone(), amount, fee, Activity::Released);
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(release.correlation_id, release.reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Override, this will release part of the escrow account to some recipient.
+        /// The account stays with the remainder and is removed once it reaches zero.
+        #[ink(message, selector = 0xB607AD63)]
+        pub fn force_release_partial(&mut self,
+            account: AccountId,
+            recipient: AccountId,
+            amount: u128,
+            key: Option<Hash>) -> Result<Option<Receipt>, ContractError> {
+
+            // Override the release of the escrow account can only be done by
+            // a releaser.
+            let caller = self.env().caller();
+            if !self.has_role(Role::Releaser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            if !self.feature_enabled(FEATURE_FORCE_RELEASE) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeatureDisabled),
+                });
+                return Err(Error::EscrowFeatureDisabled.into());
+            }
+
+            self.propose_or_execute(PendingRelease {
+                manager: caller,
+                account,
+                recipient,
+                recipient_bps: 10_000,
+                amount: Some(amount),
+                key,
+                initiated_at: self.env().block_number(),
+                refund: false,
+            })
+        }
+
+        /// Override the release of the escrow account of a reference, paid in full to
+        /// its recorded recipient
+        #[ink(message, selector = 0xC272A486)]
+        pub fn force_release_by_reference(&mut self, reference: u16) -> Result<(), ContractError> {
+
+            // Override the release of the escrow account can only be done by
+            // a releaser.
+            let caller = self.env().caller();
+            if !self.has_role(Role::Releaser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            if !self.feature_enabled(FEATURE_FORCE_RELEASE) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeatureDisabled),
+                });
+                return Err(Error::EscrowFeatureDisabled.into());
+            }
+
+            let Some(account) = self.references.get(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            self.force_release_entry(caller, account).map(|_| ())
+        }
+
+        /// Override the release of a batch of escrow accounts, each paid in full to
+        /// its recorded recipient. Each entry emits its own event and a failed entry
+        /// does not stop the batch. The batch stops before running out of gas and
+        /// returns the cursor of the first unprocessed entry.
+        #[ink(message, selector = 0x5498473B)]
+        pub fn force_release_batch(&mut self, accounts: Vec<AccountId>) -> Result<BatchOutcome, Error> {
+
+            // Override the release of the escrow accounts can only be done by
+            // a releaser.
+            let caller = self.env().caller();
+            if !self.has_role(Role::Releaser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if !self.feature_enabled(FEATURE_FORCE_RELEASE) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeatureDisabled),
+                });
+                return Err(Error::EscrowFeatureDisabled);
+            }
+
+            let total = accounts.len() as u32;
+            let mut outcome = BatchOutcome::default();
+            for (n, account) in accounts.into_iter().enumerate() {
+                if self.batch_exhausted(caller, n as u32, total) {
+                    outcome.cursor = Some(n as u32);
+                    break;
+                }
+                let error = match self.force_release_entry(caller, account) {
+                    Ok(_) => None,
+                    Err(ContractError::Internal(error)) => Some(error),
+                    // The single transfer of the entry failed, the account is kept
+                    // and may be converted into a refund
+                    Err(ContractError::Runtime(_)) => {
+                        self.release_failures.insert(account, &self.env().block_number());
+                        self.env().emit_event(EscrowEvent {
+                            operator: caller,
+                            status: EscrowStatus::EmitError(Error::EscrowTransferFailed),
+                        });
+                        Some(Error::EscrowTransferFailed)
+                    }
+                };
+                outcome.results.push(BatchResult { account, error });
+            }
+
+            self.assert_invariants();
+
+            Ok(outcome)
+        }
+
+        /// Override the release of one batch entry to its recorded recipient, stored
+        /// as a pending release under the dual control or the quorum
+        fn force_release_entry(&mut self, caller: AccountId, account: AccountId) -> Result<Option<Receipt>, ContractError> {
+            let recipient = match self.accounts.get(account) {
+                None => Err(Error::EscrowAccountNotFound),
+                Some(a) => a.recipient.local_account().ok_or(Error::EscrowRecipientUnsupported),
+            };
+            let recipient = match recipient {
+                Ok(recipient) => recipient,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            self.propose_or_execute(PendingRelease {
+                manager: caller,
+                account,
+                recipient,
+                recipient_bps: 10_000,
+                amount: None,
+                key: None,
+                initiated_at: self.env().block_number(),
+                refund: false,
+            })
+        }
+
+        /// Take a released part off an escrow account, the third-party contributions
+        /// shrink pro rata. An emptied account is removed with the activity. `fee` is
+        /// the protocol fee already charged on the part.
+        fn release_part(&mut self, mut account: Account, amount: u128, fee: u128, activity: Activity) -> Success {
+            let reference = account.reference;
+            if amount >= account.balance {
+                self.remove_account(&account, activity);
+                return Success::EscrowAccountReleased { reference, amount, fee };
+            }
+
+            let remaining = account.balance - amount;
+            let mut contributions = self.contributions.get(reference).unwrap_or_default();
+            if !contributions.is_empty() {
+                for c in contributions.iter_mut() {
+                    c.amount = c.amount.saturating_mul(remaining) / account.balance;
+                }
+                self.contributions.insert(reference, &contributions);
+            }
+
+            if let Some(mut vesting) = self.vestings.get(reference) {
+                vesting.released = vesting.released.saturating_add(amount);
+                self.vestings.insert(reference, &vesting);
+            }
+
+            account.balance = remaining;
+            self.save_account(&account);
+            self.debit(account.asset_id, amount);
+            self.record_release(&account, amount, &activity);
+            self.record_history(reference, Activity::PartiallyReleased);
+
+            Success::EscrowAccountPartiallyReleased { reference, amount, fee, remaining }
+        }
+
+        /// Check if the escrow account can be released by its owner, returns the
+        /// account
+        fn check_release(&self, account: AccountId) -> Result<Account, Error> {
+
+            // Check if the escrow allows releases
+            if self.releases_blocked() {
+                return Err(Error::EscrowIsClose);
+            }
+            if self.is_paused(PAUSE_RELEASE) {
+                return Err(Error::EscrowOperationPaused);
+            }
+
+            // Releases are paused until the owner acknowledges a detected insolvency
+            if self.insolvency_paused {
+                return Err(Error::EscrowInsolvencyPaused);
+            }
+
+            // Locate the escrow account
+            let a = self.accounts.get(account).ok_or(Error::EscrowAccountNotFound)?;
+
+            // Frozen accounts cannot be released
+            if a.status == 0 {
+                return Err(Error::EscrowAccountFrozen);
+            }
+
+            // Disputed accounts are settled by the arbiter
+            if a.status == 2 {
+                return Err(Error::EscrowAccountDisputed);
+            }
+
+            // Pending accounts wait for the recipient acceptance
+            if a.status == 3 {
+                return Err(Error::EscrowAccountPending);
+            }
+
+            // The depositor paused the release of its account
+            if self.env().block_number() < a.paused_until {
+                return Err(Error::EscrowAccountPaused);
+            }
+
+            // The lock period set when the account was added has not passed
+            if self.env().block_number() < a.release_after {
+                return Err(Error::EscrowStillLocked);
+            }
+
+            // The recipient must have confirmed its address
+            if !a.confirmed {
+                return Err(Error::EscrowRecipientNotConfirmed);
+            }
+
+            // The payout template must still exist
+            if let Some(id) = a.template {
+                if !self.templates.contains(id) {
+                    return Err(Error::EscrowTemplateNotFound);
+                }
+            }
+
+            // Funds never go to a blocked recipient, directly or as a template share
+            let blocked_share = a.template
+                .and_then(|id| self.templates.get(id))
+                .is_some_and(|t| t.shares.iter().any(|s| self.account_blocked(s.recipient)));
+            if self.recipient_blocked(&a.recipient) || blocked_share {
+                return Err(Error::RecipientBlocked);
+            }
+
+            // A vesting account needs a vested part to release
+            if self.vestings.contains(a.reference) && self.vested_amount(&a) == 0 {
+                return Err(Error::EscrowNotVested);
+            }
+
+            // The escrow must hold the funds it is about to pay out
+            if self.funds_short(a.asset_id, self.vested_amount(&a)) {
+                return Err(Error::InsufficientEscrowFunds);
+            }
+
+            // A mutual-consent account needs the approval of both parties
+            if self.release_consents.get(a.reference).is_some_and(|c| !(c.depositor && c.recipient)) {
+                return Err(Error::EscrowReleaseNotApproved);
+            }
+
+            Ok(a)
+        }
+
+        /// Part of the balance of an escrow account its vesting schedule lets go, the
+        /// whole balance without a schedule
+        fn vested_amount(&self, account: &Account) -> u128 {
+            let Some(vesting) = self.vestings.get(account.reference) else {
+                return account.balance;
+            };
+
+            let elapsed = self.env().block_number().saturating_sub(vesting.start);
+            if elapsed < vesting.cliff {
+                return 0;
+            }
+
+            let total = vesting.released.saturating_add(account.balance);
+            let vested = if elapsed >= vesting.duration {
+                total
+            } else {
+                let (elapsed, duration) = (elapsed as u128, vesting.duration as u128);
+                total
+                    .checked_mul(elapsed)
+                    .map_or(total / duration * elapsed, |amount| amount / duration)
+            };
+            vested.saturating_sub(vesting.released).min(account.balance)
+        }
+
+        /// Set a linear vesting schedule on an escrow account, `release` then pays the
+        /// part vested since the last release. A new schedule keeps the amount
+        /// already released under the previous one.
+        #[ink(message, selector = 0xEBB9231B)]
+        pub fn set_vesting(&mut self,
+            reference: u16,
+            start: BlockNumber,
+            duration: BlockNumber,
+            cliff: BlockNumber) -> Result<(), Error> {
+
+            // Only the manager can set a vesting schedule
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let account = match self.account_by_reference(reference) {
+                _ if !self.feature_enabled(FEATURE_VESTING) => Err(Error::EscrowFeatureDisabled),
+                _ if duration == 0 || cliff > duration => Err(Error::EscrowVestingInvalid),
+                Some(a) if a.status == 2 => Err(Error::EscrowAccountDisputed),
+                Some(a) => Ok(a),
+                None => Err(Error::EscrowAccountNotFound),
+            };
+            let account = match account {
+                Ok(a) => a,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error);
+                }
+            };
+
+            let released = self.vestings.get(reference).map_or(0, |v| v.released);
+            self.vestings.insert(reference, &Vesting { start, duration, cliff, released });
+
+            let operation = Success::EscrowVestingSet { reference };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(account.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Require, or stop requiring, the approval of both the depositor and the
+        /// recipient before an escrow account is released, as in a buyer/seller escrow
+        #[ink(message, selector = 0x2DB3B22F)]
+        pub fn set_mutual_release(&mut self, reference: u16, enabled: bool) -> Result<(), Error> {
+
+            // Only the manager can set the release mode
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            // The recipient must be able to sign its approval here
+            let account = match self.account_by_reference(reference) {
+                Some(a) if enabled && a.recipient.local_account().is_none() => Err(Error::EscrowRecipientUnsupported),
+                Some(a) => Ok(a),
+                None => Err(Error::EscrowAccountNotFound),
+            };
+            let account = match account {
+                Ok(a) => a,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error);
+                }
+            };
+
+            if enabled {
+                if !self.release_consents.contains(reference) {
+                    self.release_consents.insert(reference, &ReleaseConsent::default());
+                }
+            } else {
+                self.release_consents.remove(reference);
+            }
+
+            let operation = Success::EscrowMutualReleaseSet { reference, enabled };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(account.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Approve the release of a mutual-consent escrow account, called by its
+        /// depositor or its recipient. The second approval releases the account when
+        /// nothing else holds it, otherwise the depositor releases it later.
+        #[ink(message, selector = 0xAA0F77C8)]
+        pub fn consent_release(&mut self, reference: u16) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+
+            // Locate the account by reference, the caller must be one of its parties
+            let consent = match self.account_by_reference(reference) {
+                Some(a) if caller != a.account && a.recipient.local_account() != Some(caller) => Err(Error::BadOrigin),
+                Some(a) if a.status == 2 => Err(Error::EscrowAccountDisputed),
+                Some(a) => self.release_consents
+                    .get(reference)
+                    .map(|c| (a, c))
+                    .ok_or(Error::EscrowAccountNotFound),
+                None => Err(Error::EscrowAccountNotFound),
+            };
+            let (account, mut consent) = match consent {
+                Ok(consent) => consent,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            if caller == account.account {
+                consent.depositor = true;
+            }
+            if account.recipient.local_account() == Some(caller) {
+                consent.recipient = true;
+            }
+            self.release_consents.insert(reference, &consent);
+
+            let operation = Success::EscrowReleaseConsented { reference, approver: caller };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(account.correlation_id, reference, operation);
+
+            if consent.depositor && consent.recipient && self.check_release(account.account).is_ok() {
+                return self.release_attested(account.account, None);
+            }
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the release approvals of a mutual-consent escrow account
+        #[ink(message, selector = 0x2092943C)]
+        pub fn get_release_consent(&self, reference: u16) -> Option<ReleaseConsent> {
+            self.release_consents.get(reference)
+        }
+
+        /// Get the vesting schedule of an escrow account
+        #[ink(message, selector = 0x6D435B9B)]
+        pub fn get_vesting(&self, reference: u16) -> Option<Vesting> {
+            self.vestings.get(reference)
+        }
+
+        /// Get the amount of an escrow account releasable now under its vesting
+        /// schedule
+        #[ink(message, selector = 0xEAC43600)]
+        pub fn get_vested(&self, reference: u16) -> u128 {
+            self.account_by_reference(reference)
+                .map_or(0, |a| self.vested_amount(&a))
+        }
+
+        /// Transfer an amount of an escrow account to its recipient, or split it
+        /// among the shares of its payout template
+        fn payout(&mut self, account: &Account, total: u128) -> Result<(), RuntimeError> {
+            let Some(template) = account.template.and_then(|id| self.templates.get(id)) else {
+                return self.pay_recipient(&account.recipient, account.asset_id, total, account.reference);
+            };
+            let shares = template.shares;
+
+            // The last share receives the rounding remainder
+            let mut remaining = total;
+            for (n, share) in shares.iter().enumerate() {
+                let amount = if n + 1 == shares.len() {
+                    remaining
+                } else {
+                    total.saturating_mul(share.bps as u128) / 10_000
+                };
+                remaining = remaining.saturating_sub(amount);
+
+                if amount == 0 {
+                    continue;
+                }
+
+                let target = self.payout_address(share.recipient);
+                self.transfer(account.asset_id, target, amount, Some(account.reference))?;
+            }
+
+            Ok(())
+        }
+
+        /// Pay an escrow account recipient with the transfer flavor of its execution
+        /// environment. Remote payouts are dispatched right away through
+        /// `pallet_xcm::transfer_assets`, even when transfers are deferred.
+        fn pay_recipient(&mut self,
+            recipient: &Recipient,
+            asset_id: u128,
+            amount: u128,
+            reference: u16) -> Result<(), RuntimeError> {
+
+            if amount == 0 {
+                return Ok(());
+            }
+
+            if let Recipient::Remote { dest, beneficiary } = recipient {
+                // Only `pallet_assets` assets have an XCM asset location here
+                match self.backend_of(asset_id) {
+                    TransferBackend::Assets => {}
+                    TransferBackend::Psp22(_) => return Err(RuntimeError::TokenTransferFailed),
+                    TransferBackend::Native => return Err(RuntimeError::NativeTransferFailed),
+                }
+                if asset_id == self.asset_id {
+                    self.record_outflow(amount);
+                }
+                return self
+                    .dispatch(&RuntimeCall::PolkadotXcm(XcmCall::TransferAssets {
+                        dest: xcm::Encoded(dest.clone()),
+                        beneficiary: xcm::Encoded(beneficiary.clone()),
+                        assets: xcm::local_assets(asset_id, amount),
+                        fee_asset_item: 0,
+                        weight_limit: WeightLimit::Unlimited,
+                    }))
+                    .map_err(|_| RuntimeError::CallRuntimeFailed);
+            }
+
+            match recipient.local_account() {
+                Some(account) => {
+                    let target = self.payout_address(account);
+                    self.transfer(asset_id, target, amount, Some(reference))
+                }
+                None => Ok(()),
+            }
+        }
+
+        /// Transfer escrow funds, the transfer is queued for `flush` when transfers are
+        /// deferred
+        fn transfer(&mut self,
+            asset_id: u128,
+            target: AccountId,
+            amount: u128,
+            reference: Option<u16>) -> Result<(), RuntimeError> {
+
+            if reference.is_some() && asset_id == self.asset_id {
+                self.record_outflow(amount);
+            }
+
+            // Account transfers below the minimum balance would fail the runtime call
+            if let Some(reference) = reference {
+                if self.dust_policy == 1 && asset_id == self.asset_id && amount < self.dust_threshold {
+                    self.dust_pot = self.dust_pot.saturating_add(amount);
+                    self.env().emit_event(DustCollected {
+                        reference,
+                        target,
+                        amount,
+                    });
+                    return Ok(());
+                }
+            }
+
+            if self.deferred_transfers {
+                self.enqueue_transfer(PendingTransfer {
+                    asset_id,
+                    target,
+                    amount,
+                    reference,
+                    attempts: 0,
+                });
+                return Ok(());
+            }
+
+            self.send(asset_id, target, amount)
+        }
+
+        /// Read the asset balance of the escrow before releases, through the assets
+        /// chain extension for `pallet_assets` escrows, which the runtime must provide
+        #[ink(message, selector = 0x1114AB9B)]
+        pub fn set_balance_checks(&mut self, enabled: bool) -> Result<(), Error> {
+
+            // Only the owner can enable the balance reads
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.balance_checks = enabled;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the balance of an asset held by the escrow as read from its backend,
+        /// `None` while balance checks are off
+        #[ink(message, selector = 0xD828E265)]
+        pub fn escrow_funds(&self, asset_id: u128) -> Option<u128> {
+            if !self.balance_checks {
+                return None;
+            }
+
+            let contract = self.env().account_id();
+            let funds = match self.backend_of(asset_id) {
+                TransferBackend::Assets => self.env().extension().balance_of(asset_id, contract),
+                TransferBackend::Psp22(token) => {
+                    let token: ink::contract_ref!(PSP22) = token.into();
+                    token.balance_of(contract)
+                }
+                TransferBackend::Native => self.env().balance(),
+            };
+            Some(funds)
+        }
+
+        /// Check if the escrow holds less of an asset than a payout and the transfers
+        /// already queued in it need, never short while balance checks are off
+        fn funds_short(&self, asset_id: u128, amount: u128) -> bool {
+            let queued = if asset_id == self.asset_id { self.queued_amount } else { 0 };
+            self.escrow_funds(asset_id)
+                .is_some_and(|funds| funds < amount.saturating_add(queued))
+        }
+
+        /// Dispatch a runtime call addressed with the pallet and call indices of the runtime
+        fn dispatch(&self, call: &RuntimeCall) -> ink::env::Result<()> {
+            self.env().call_runtime(&IndexedCall {
+                call,
+                indices: self.pallet_indices,
+            })
+        }
+
+        /// Get the pallet and call indices runtime calls are addressed with
+        #[ink(message, selector = 0x18A08CA7)]
+        pub fn get_pallet_indices(&self) -> PalletIndices {
+            self.pallet_indices
+        }
+
+        /// `pallet_assets` transfer out of the escrow through the transfer method
+        fn assets_transfer(&self, id: u128, target: AccountId, amount: u128) -> RuntimeCall {
+            match self.transfer_method {
+                TransferMethod::Transfer => RuntimeCall::Assets(AssetsCall::Transfer {
+                    id,
+                    target: target.into(),
+                    amount,
+                }),
+                TransferMethod::TransferAll if self.escrow_funds(id) == Some(amount) => {
+                    RuntimeCall::Assets(AssetsCall::TransferAll {
+                        id,
+                        dest: target.into(),
+                        keep_alive: false,
+                    })
+                }
+                TransferMethod::KeepAlive | TransferMethod::TransferAll => {
+                    RuntimeCall::Assets(AssetsCall::TransferKeepAlive {
+                        id,
+                        target: target.into(),
+                        amount,
+                    })
+                }
+            }
+        }
+
+        /// Set the `pallet_assets` call paying out of the escrow, `KeepAlive` keeps a
+        /// release from reaping the escrow asset account below the minimum balance
+        #[ink(message, selector = 0x9725C40A)]
+        pub fn set_transfer_method(&mut self, method: TransferMethod) -> Result<(), Error> {
+
+            // Only the owner can change the transfer method
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.transfer_method = method;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the `pallet_assets` call paying out of the escrow
+        #[ink(message, selector = 0x9D345C90)]
+        pub fn get_transfer_method(&self) -> TransferMethod {
+            self.transfer_method
+        }
+
+        /// Check an asset transfer through the assets chain extension before it is
+        /// dispatched when balance checks are on. `call_runtime` never returns the
+        /// `DispatchError` of the call, so the reason of a failure is only known
+        /// up front.
+        fn transfer_check(&self, asset_id: u128, from: AccountId, to: AccountId, amount: u128) -> Result<(), RuntimeError> {
+            if !self.balance_checks {
+                return Ok(());
+            }
+
+            match self.env().extension().transfer_check(asset_id, from, to, amount) {
+                0 => Ok(()),
+                code => Err(RuntimeError::from_transfer_check(code)),
+            }
+        }
+
+        /// Move an asset out of the escrow through the backend holding it
+        fn send(&mut self, asset_id: u128, target: AccountId, amount: u128) -> Result<(), RuntimeError> {
+            match self.backend_of(asset_id) {
+                TransferBackend::Assets => {
+                    self.transfer_check(asset_id, self.env().account_id(), target, amount)?;
+                    self.dispatch(&self.assets_transfer(asset_id, target, amount))
+                        .map_err(|_| RuntimeError::CallRuntimeFailed)
+                }
+                TransferBackend::Psp22(token) => {
+                    let mut token: ink::contract_ref!(PSP22) = token.into();
+                    token
+                        .transfer(target, amount, Vec::new())
+                        .map_err(|_| RuntimeError::TokenTransferFailed)
+                }
+                TransferBackend::Native => self.env()
+                    .transfer(target, amount)
+                    .map_err(|_| RuntimeError::NativeTransferFailed),
+            }
+        }
+
+        /// Pull an asset the owner approved into the escrow through the backend
+        /// holding it. The native token has no approvals, it only arrives as the value
+        /// of a payable call.
+        fn pull(&mut self, asset_id: u128, owner: AccountId, amount: u128) -> Result<(), RuntimeError> {
+            let escrow = self.env().account_id();
+            match self.backend_of(asset_id) {
+                TransferBackend::Assets => {
+                    self.transfer_check(asset_id, owner, escrow, amount)?;
+                    self.dispatch(&RuntimeCall::Assets(AssetsCall::TransferApproved {
+                        id: asset_id,
+                        owner: owner.into(),
+                        destination: escrow.into(),
+                        amount,
+                    }))
+                    .map_err(|_| RuntimeError::CallRuntimeFailed)
+                }
+                TransferBackend::Psp22(token) => {
+                    let mut token: ink::contract_ref!(PSP22) = token.into();
+                    token
+                        .transfer_from(owner, escrow, amount, Vec::new())
+                        .map_err(|_| RuntimeError::TokenTransferFailed)
+                }
+                TransferBackend::Native => Err(RuntimeError::NativeTransferFailed),
+            }
+        }
+
+        /// Approve a delegate to pull an asset out of the escrow through the backend
+        /// holding it
+        fn approve_pull(&mut self, asset_id: u128, delegate: AccountId, amount: u128) -> Result<(), RuntimeError> {
+            match self.backend_of(asset_id) {
+                TransferBackend::Assets => self
+                    .dispatch(&RuntimeCall::Assets(AssetsCall::ApproveTransfer {
+                        id: asset_id,
+                        delegate: delegate.into(),
+                        amount,
+                    }))
+                    .map_err(|_| RuntimeError::CallRuntimeFailed),
+                TransferBackend::Psp22(token) => {
+                    let mut token: ink::contract_ref!(PSP22) = token.into();
+                    token
+                        .approve(delegate, amount)
+                        .map_err(|_| RuntimeError::TokenTransferFailed)
+                }
+                TransferBackend::Native => Err(RuntimeError::NativeTransferFailed),
+            }
+        }
+
+        /// Backend holding an asset, only the escrow asset can live outside
+        /// `pallet_assets`
+        fn backend_of(&self, asset_id: u128) -> TransferBackend {
+            if asset_id == self.asset_id {
+                self.backend
+            } else {
+                TransferBackend::Assets
+            }
+        }
+
+        /// Add an amount paid out of the escrow accounts to the current outflow bucket
+        fn record_outflow(&mut self, amount: u128) {
+            let bucket = self.env().block_number() / OUTFLOW_BUCKET_BLOCKS;
+            let slot = bucket % OUTFLOW_BUCKETS;
+            let total = match self.outflow_buckets.get(slot) {
+                Some((stored, total)) if stored == bucket => total.saturating_add(amount),
+                _ => amount,
+            };
+            self.outflow_buckets.insert(slot, &(bucket, total));
+        }
+
+        /// Get the escrow asset paid out of the accounts (releases, refunds, reclaims)
+        /// during the last `blocks` blocks, so a risk monitor can detect anomalous
+        /// outflow velocity. The window is rounded up to whole buckets of
+        /// `OUTFLOW_BUCKET_BLOCKS` and capped at `OUTFLOW_BUCKETS` buckets.
+        #[ink(message, selector = 0xE2A2206D)]
+        pub fn get_outflow(&self, blocks: BlockNumber) -> u128 {
+            let current = self.env().block_number() / OUTFLOW_BUCKET_BLOCKS;
+            let buckets = blocks.div_ceil(OUTFLOW_BUCKET_BLOCKS).min(OUTFLOW_BUCKETS);
+            (0..buckets.min(current + 1))
+                .map(|n| current - n)
+                .filter_map(|bucket| match self.outflow_buckets.get(bucket % OUTFLOW_BUCKETS) {
+                    Some((stored, total)) if stored == bucket => Some(total),
+                    _ => None,
+                })
+                .fold(0u128, |sum, total| sum.saturating_add(total))
+        }
+
+        /// Push a transfer at the tail of the queue
+        fn enqueue_transfer(&mut self, pending: PendingTransfer) {
+            if pending.asset_id == self.asset_id {
+                self.queued_amount = self.queued_amount.saturating_add(pending.amount);
+            }
+            self.transfer_queue.insert(self.queue_tail, &pending);
+            self.queue_tail = self.queue_tail.wrapping_add(1);
+        }
+
+        /// Protocol fee on an amount released to a recipient, zero while the fees
+        /// feature is disabled
+        fn compute_fee(&self, amount: u128) -> u128 {
+            if !self.feature_enabled(FEATURE_FEES) {
+                return 0;
+            }
+
+            amount.saturating_mul(self.fee_bps as u128) / 10_000
+        }
+
+        /// Transfer the protocol fee on an amount released to a recipient to the fee
+        /// collector, returns the fee
+        fn charge_fee(&mut self, asset_id: u128, amount: u128, reference: u16) -> Result<u128, RuntimeError> {
+            let fee = self.compute_fee(amount);
+            if fee > 0 {
+                self.transfer(asset_id, self.fee_collector, fee, Some(reference))?;
+            }
+
+            Ok(fee)
+        }
+
+        /// Refund the balance of an escrow account pro-rata to the depositor and the
+        /// third-party contributors
+        fn refund_contributors(&mut self, account: &Account) -> Result<(), RuntimeError> {
+            let balance = account.balance;
+            let reference = account.reference;
+            let contributions = self.get_contributions(reference);
+            let total = contributions
+                .iter()
+                .fold(0u128, |total, c| total.saturating_add(c.amount));
+
+            // The last contributor receives the rounding remainder
+            let mut remaining = balance;
+            for (n, c) in contributions.iter().enumerate() {
+                let amount = if n + 1 == contributions.len() || total == 0 {
+                    remaining
+                } else {
+                    balance.saturating_mul(c.amount) / total
+                };
+                remaining = remaining.saturating_sub(amount);
+
+                if amount == 0 {
+                    continue;
+                }
+
+                self.transfer(account.asset_id, c.contributor, amount, Some(reference))?;
+            }
+
+            Ok(())
+        }
+
+        /// Simulate the release of a batch of escrow accounts without changing state
+        #[ink(message, selector = 0x94B4E471)]
+        pub fn simulate_release_batch(&self, accounts: Vec<AccountId>) -> Vec<SimResult> {
+            accounts
+                .into_iter()
+                .map(|account| match self.check_release(account) {
+                    Ok(a) => {
+                        let amount = self.vested_amount(&a);
+                        SimResult {
+                            account,
+                            amount,
+                            fee: self.compute_fee(amount),
+                            recipient: Some(a.recipient),
+                            error: None,
+                        }
+                    }
+                    Err(error) => SimResult {
+                        account,
+                        amount: 0,
+                        fee: 0,
+                        recipient: None,
+                        error: Some(error),
+                    },
+                })
+                .collect()
+        }
+
+        /// Set the order in which the matured-release sweep pays the accounts, with the
+        /// priority list of references for `ReleaseOrder::Priority`
+        #[ink(message, selector = 0x3BB95C46)]
+        pub fn set_release_order(&mut self, order: ReleaseOrder, priority: Vec<u16>) -> Result<(), Error> {
+
+            // Only the manager can order the payouts
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.release_order = order;
+            self.release_priority = priority;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the order of the matured-release sweep and its priority list
+        #[ink(message, selector = 0x10ECF997)]
+        pub fn get_release_order(&self) -> (ReleaseOrder, Vec<u16>) {
+            (self.release_order, self.release_priority.clone())
+        }
+
+        /// Get the position of a matured escrow account in the sweep order, 0 is paid
+        /// first. `None` if the account is not matured or cannot be released.
+        #[ink(message, selector = 0xB24FF8B6)]
+        pub fn release_position(&self, reference: u16) -> Option<u32> {
+            self.matured_in_order()
+                .iter()
+                .position(|a| a.reference == reference)
+                .map(|position| position as u32)
+        }
+
+        /// Release matured escrow accounts, whose lock period has passed, to their
+        /// recipients in the release order, while the liquidity `available` covers
+        /// them. The sweep stops at the first account it cannot cover so that no
+        /// account is overtaken.
+        #[ink(message, selector = 0xF0F9719D)]
+        pub fn sweep_matured(&mut self, available: u128, limit: u32) -> Result<(), ContractError> {
+
+            // Only the manager reports the available liquidity
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            let mut released = 0u32;
+            let mut amount = 0u128;
+            for account in self.matured_in_order().into_iter().take(limit as usize) {
+                let total = amount.saturating_add(account.balance);
+                if total > available {
+                    break;
+                }
+
+                let fee = self.charge_fee(account.asset_id, account.balance, account.reference)?;
+                self.payout(&account, account.balance - fee)?;
+                self.remove_account(&account, Activity::Released);
+
+                let operation = Success::EscrowAccountReleased {
+                    reference: account.reference,
+                    amount: account.balance,
+                    fee,
+                };
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitSuccess(operation.clone()),
+                });
+                self.emit_account_event(account.correlation_id, account.reference, operation);
+
+                released += 1;
+                amount = total;
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowMaturedSwept { released, amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Matured escrow accounts that can be released, in the release order
+        fn matured_in_order(&self) -> Vec<Account> {
+            let now = self.env().block_number();
+            let mut matured: Vec<Account> = self.account_list()
+                .into_iter()
+                .filter(|a| a.release_after != 0 && now >= a.release_after)
+                .filter(|a| !self.vestings.contains(a.reference))
+                .filter(|a| self.check_release(a.account).is_ok())
+                .collect();
+
+            match self.release_order {
+                ReleaseOrder::Fifo => matured.sort_by_key(|a| a.added_at),
+                ReleaseOrder::SmallestFirst => matured.sort_by_key(|a| a.balance),
+                ReleaseOrder::Priority => matured.sort_by_key(|a| {
+                    let rank = self.release_priority
+                        .iter()
+                        .position(|r| *r == a.reference)
+                        .unwrap_or(usize::MAX);
+                    (rank, a.added_at)
+                }),
+            }
+            matured
+        }
+
+        /// Override, this will release the escrow account to some recipient. A legacy
+        /// message, a rejected release returns `Ok` and reports the error through the
+        /// event only, a failed transfer returns the runtime error.
+        #[ink(message, selector = 0xAECC9EBF)]
+        pub fn force_release(&mut self,
+            account: AccountId,
+            recipient: AccountId,
+            key: Option<Hash>) -> Result<Option<Receipt>, ContractError> {
+            match self.force_release_split(account, recipient, 10_000, key) {
+                Err(ContractError::Internal(_)) => Ok(None),
+                result => result,
+            }
+        }
+
+        /// Override, this will split the escrow account between the recipient, which
+        /// receives `recipient_bps` basis points, and the depositor, which receives the
+        /// rest. Both transfers revert together if either fails.
+        #[ink(message, selector = 0xBE71D9AC)]
+        pub fn force_release_split(&mut self,
+            account: AccountId,
+            recipient: AccountId,
+            recipient_bps: u16,
+            key: Option<Hash>) -> Result<Option<Receipt>, ContractError> {
+
+            // Override the release of the escrow account can only be done by
+            // a releaser.
+            let caller = self.env().caller();
+            if !self.has_role(Role::Releaser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            } 
+
+            if !self.feature_enabled(FEATURE_FORCE_RELEASE) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeatureDisabled),
+                });
+                return Err(Error::EscrowFeatureDisabled.into());
+            }
+
+            if recipient_bps > 10_000 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowSplitInvalid),
+                });
+                return Err(Error::EscrowSplitInvalid.into());
+            }
+
+            self.propose_or_execute(PendingRelease {
+                manager: caller,
+                account,
+                recipient,
+                recipient_bps,
+                amount: None,
+                key,
+                initiated_at: self.env().block_number(),
+                refund: false,
+            })
+        }
+
+        /// Execute a manager override release, or store it as a pending release
+        /// waiting for the quorum approvals or the owner countersignature under dual
+        /// control. A proposing quorum manager approves its own release. Returns the
+        /// receipt of a keyed release, a proposal is receipted until it executes.
+        fn propose_or_execute(&mut self, release: PendingRelease) -> Result<Option<Receipt>, ContractError> {
+            let manager = release.manager;
+
+            // A replayed idempotency key is a no-op, also while the release is pending
+            if let Some(receipt) = self.replayed(manager, KeyedOperation::ForceRelease, release.key) {
+                return Ok(Some(receipt));
+            }
+
+            let approvals = if self.quorum_managers.contains(&manager) { 1 } else { 0 };
+            if self.quorum_threshold > 0 {
+                if approvals >= self.quorum_threshold {
+                    return self.execute_force_release(manager, release);
+                }
+            } else if self.dual_control_window == 0 {
+                return self.execute_force_release(manager, release);
+            }
+
+            let id = self.pending_release_counter;
+            self.pending_release_counter = id.wrapping_add(1);
+            self.pending_releases.insert(id, &release);
+            if approvals > 0 {
+                self.release_approvals.insert(id, &ink::prelude::vec![manager]);
+            }
+
+            let (reference, balance) = self.accounts
+                .get(release.account)
+                .map(|a| (a.reference, a.balance))
+                .unwrap_or_default();
+            let receipt = self.record_receipt(
+                manager,
+                KeyedOperation::ForceRelease,
+                release.key,
+                Success::EscrowReleaseProposed { id },
+                reference,
+                release.account,
+                release.amount.unwrap_or(balance),
+            );
+
+            self.env().emit_event(EscrowEvent {
+                operator: manager,
+                status: EscrowStatus::EmitSuccess(Success::EscrowReleaseProposed { id }),
+            });
+
+            self.assert_invariants();
+
+            Ok(receipt)
+        }
+
+        /// Countersign a pending manager override release, which executes it. Under
+        /// the quorum, approve it as a quorum manager, the approval reaching the
+        /// threshold executes it.
+        #[ink(message, selector = 0x8FE8B5D6)]
+        pub fn approve_release(&mut self, id: u32) -> Result<(), ContractError> {
+            if self.quorum_threshold > 0 {
+                return self.approve_quorum_release(id);
+            }
+
+            // Only the owner countersigns the manager overrides
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            let Some(release) = self.pending_releases.take(id) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowPendingReleaseNotFound),
+                });
+                return Err(Error::EscrowPendingReleaseNotFound.into());
+            };
+
+            // An expired proposal can only be cancelled, the manager proposes it again
+            if self.env().block_number() > release.initiated_at.saturating_add(self.dual_control_window) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowPendingReleaseExpired),
+                });
+                return Err(Error::EscrowPendingReleaseExpired.into());
+            }
+
+            self.execute_force_release(caller, release).map(|_| ())
+        }
+
+        /// Approve a pending manager override release as a quorum manager
+        fn approve_quorum_release(&mut self, id: u32) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let release = match self.pending_releases.get(id) {
+                _ if !self.quorum_managers.contains(&caller) => Err(Error::BadOrigin),
+                None => Err(Error::EscrowPendingReleaseNotFound),
+                Some(release) if self.env().block_number()
+                    > release.initiated_at.saturating_add(self.quorum_window) => Err(Error::EscrowPendingReleaseExpired),
+                Some(release) => Ok(release),
+            };
+            let mut approvals = self.release_approvals.get(id).unwrap_or_default();
+            let release = match release {
+                Ok(_) if approvals.contains(&caller) => Err(Error::EscrowAlreadyApproved),
+                result => result,
+            };
+            let release = match release {
+                Ok(release) => release,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            // Approvals of managers since removed from the quorum no longer count
+            approvals.push(caller);
+            approvals.retain(|a| self.quorum_managers.contains(a));
+            let count = approvals.len() as u8;
+            if count >= self.quorum_threshold {
+                self.pending_releases.remove(id);
+                self.release_approvals.remove(id);
+                return self.execute_force_release(caller, release).map(|_| ());
+            }
+            self.release_approvals.insert(id, &approvals);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowReleaseApproved { id, approvals: count }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the quorum of managers approving override releases, `threshold`
+        /// approvals within `window` blocks execute a release. A threshold of 0
+        /// disables the quorum.
+        #[ink(message, selector = 0x3451AFE0)]
+        pub fn set_quorum(&mut self,
+            managers: Vec<AccountId>,
+            threshold: u8,
+            window: BlockNumber) -> Result<(), Error> {
+
+            // Only the owner can change the quorum
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if threshold as usize > managers.len() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowQuorumInvalid),
+                });
+                return Err(Error::EscrowQuorumInvalid);
+            }
+
+            self.quorum_managers = managers;
+            self.quorum_threshold = threshold;
+            self.quorum_window = window;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the quorum (managers, threshold, window)
+        #[ink(message, selector = 0xA4829457)]
+        pub fn get_quorum(&self) -> (Vec<AccountId>, u8, BlockNumber) {
+            (self.quorum_managers.clone(), self.quorum_threshold, self.quorum_window)
+        }
+
+        /// Get the quorum managers that approved a pending release
+        #[ink(message, selector = 0xE96A4A20)]
+        pub fn get_release_approvals(&self, id: u32) -> Vec<AccountId> {
+            self.release_approvals.get(id).unwrap_or_default()
+        }
+
+        /// Cancel a pending manager override release
+        #[ink(message, selector = 0xEEE2F599)]
+        pub fn cancel_release(&mut self, id: u32) -> Result<(), Error> {
+
+            // Either side of the dual control or any quorum manager can cancel
+            let caller = self.env().caller();
+            if caller != self.owner && caller != self.manager && !self.quorum_managers.contains(&caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if self.pending_releases.take(id).is_none() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowPendingReleaseNotFound),
+                });
+                return Err(Error::EscrowPendingReleaseNotFound);
+            }
+            self.release_approvals.remove(id);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowReleaseCancelled { id }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get a pending manager override release
+        #[ink(message, selector = 0x81454D20)]
+        pub fn get_pending_release(&self, id: u32) -> Option<PendingRelease> {
+            self.pending_releases.get(id)
+        }
+
+        /// Require the owner to countersign manager override releases within `window`
+        /// blocks, 0 executes them right away
+        #[ink(message, selector = 0x6BB7AE62)]
+        pub fn set_dual_control(&mut self, window: BlockNumber) -> Result<(), Error> {
+
+            // Only the owner can change the dual control
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.dual_control_window = window;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Release an escrow account as decided by a manager override, split between
+        /// the recipient and the depositor or partially to the recipient. The
+        /// `caller` is the manager, or the owner countersigning it. The receipt of a
+        /// keyed release is recorded for the manager that proposed it.
+        fn execute_force_release(&mut self, caller: AccountId, release: PendingRelease) -> Result<Option<Receipt>, ContractError> {
+            let PendingRelease { manager, account, recipient, recipient_bps, amount, key, refund, .. } = release;
+
+            // Check if the escrow allows releases
+            if self.releases_blocked() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsClose),
+                });
+                return Err(Error::EscrowIsClose.into());
+            }
+
+            // Manager overrides can be paused on their own
+            if self.is_paused(PAUSE_FORCE_RELEASE) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowOperationPaused),
+                });
+                return Err(Error::EscrowOperationPaused.into());
+            }
+
+            // Releases are paused until the owner acknowledges a detected insolvency
+            if self.insolvency_paused {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowInsolvencyPaused),
+                });
+                return Err(Error::EscrowInsolvencyPaused.into());
+            }
+            
+            // Locate the account and delete it from the escrow
+            let Some(a) = self.accounts.get(account) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // Manager overrides do not preempt the arbiter
+            if a.status == 2 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDisputed),
+                });
+                return Err(Error::EscrowAccountDisputed.into());
+            }
+
+            // Nor pay out an account its recipient has not accepted
+            if a.status == 3 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountPending),
+                });
+                return Err(Error::EscrowAccountPending.into());
+            }
+
+            // Nor do they pay a blocked recipient
+            if self.account_blocked(recipient) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::RecipientBlocked),
+                });
+                return Err(Error::RecipientBlocked.into());
+            }
+
+            // Nor pay out funds the escrow does not hold
+            if self.funds_short(a.asset_id, amount.unwrap_or(a.balance)) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::InsufficientEscrowFunds),
+                });
+                return Err(Error::InsufficientEscrowFunds.into());
+            }
+
+            let reference = a.reference;
+            let (operation, receipt) = if let Some(amount) = amount {
+                if amount == 0 || amount > a.balance {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowAmountInvalid),
+                    });
+                    return Err(Error::EscrowAmountInvalid.into());
+                }
+
+                let fee = self.charge_fee(a.asset_id, amount, reference)?;
+                if amount > fee {
+                    self.transfer(a.asset_id, recipient, amount - fee, Some(reference))?;
+                }
+                self.env().emit_event(ForceReleased {
+                    account,
+                    recipient,
+                    reference,
+                    operator: caller,
+                    amount,
+                    recipient_amount: amount,
+                });
+
+                let operation = self.release_part(a.clone(), amount, fee, Activity::ForceReleased);
+                if amount == a.balance {
+                    self.record_approval(manager, a.added_at);
+                }
+                let receipt = self.record_receipt(manager, KeyedOperation::ForceRelease, key, operation.clone(), reference, account, amount);
+                (operation, receipt)
+            } else {
+                let amount = a.balance;
+
+                // The depositor share takes the rounding remainder, the protocol fee is
+                // taken off the recipient share
+                let recipient_amount = amount.saturating_mul(recipient_bps as u128) / 10_000;
+                let depositor_amount = amount - recipient_amount;
+                let fee = self.charge_fee(a.asset_id, recipient_amount, reference)?;
+                if recipient_amount > fee {
+                    self.transfer(a.asset_id, recipient, recipient_amount - fee, Some(reference))?;
+                }
+                if depositor_amount > 0 {
+                    self.transfer(a.asset_id, account, depositor_amount, Some(reference))?;
+                }
+                self.env().emit_event(ForceReleased {
+                    account,
+                    recipient,
+                    reference,
+                    operator: caller,
+                    amount,
+                    recipient_amount,
+                });
+
+                // Remove escrow account (gas efficient)
+                self.record_approval(manager, a.added_at);
+                let operation = if refund {
+                    self.remove_account(&a, Activity::ConvertedToRefund);
+                    Success::EscrowAccountConverted { reference }
+                } else {
+                    self.remove_account(&a, Activity::ForceReleased);
+                    Success::EscrowAccountReleased { reference, amount, fee }
+                };
+                let receipt = self.record_receipt(manager, KeyedOperation::ForceRelease, key, operation.clone(), reference, account, amount);
+                (operation, receipt)
+            };
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(receipt)
+        }
+
+        /// Refund the escrow account balance back to the depositor once the escrow
+        /// has been close longer than the refund delay
+        #[ink(message, selector = 0xE52DA133)]
+        pub fn refund_when_closed(&mut self) -> Result<(), ContractError> {
+
+            // Refund an escrow account by the caller
+            let caller = self.env().caller();
+
+            // Check if the escrow is close
+            if self.status == 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsOpen),
+                });
+                return Err(Error::EscrowIsOpen.into());
+            }
+
+            // Check if the escrow has been close long enough
+            if self.env().block_number() <= self.closed_at.saturating_add(self.refund_delay) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowRefundLocked),
+                });
+                return Err(Error::EscrowRefundLocked.into());
+            }
+
+            // Locate the account of the caller and delete it from the escrow
+            let Some(a) = self.accounts.get(caller) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // Return the funds to the depositor and the other contributors
+            self.refund_contributors(&a)?;
+
+            // Remove escrow account (gas efficient)
+            let reference = a.reference;
+            let amount = a.balance;
+            self.remove_account(&a, Activity::Refunded);
+
+            let operation = Success::EscrowAccountRefunded { reference, amount };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Refund the escrow account balance back to the depositor, called by the
+        /// manager or by the recipient giving up the payout
+        #[ink(message, selector = 0xA5A47441)]
+        pub fn refund(&mut self, account: AccountId) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            let Some(a) = self.accounts.get(account) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // Only the manager or the recipient of the account can refund it
+            if caller != self.manager && a.recipient != Recipient::Local(caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            // A disputed account is settled by the arbiter, with its bond
+            if a.status == 2 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDisputed),
+                });
+                return Err(Error::EscrowAccountDisputed.into());
+            }
+
+            // Return the funds to the depositor and the other contributors
+            self.refund_contributors(&a)?;
+
+            let reference = a.reference;
+            let amount = a.balance;
+            self.remove_account(&a, Activity::Refunded);
+
+            let operation = Success::EscrowAccountRefunded { reference, amount };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the block from which anyone can send the escrow account back to its
+        /// depositor, 0 removes the expiry
+        #[ink(message, selector = 0xDF0880AF)]
+        pub fn set_expiry(&mut self, reference: u16, expires_at: BlockNumber) -> Result<(), Error> {
+
+            // Only the manager sets the expiry of an account
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let Some(mut a) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            a.expires_at = expires_at;
+            self.save_account(&a);
+
+            let operation = Success::EscrowExpirySet { reference, expires_at };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Send an expired escrow account back to its depositor, callable by anyone
+        /// so funds are not stranded when a recipient disappears
+        #[ink(message, selector = 0x7DB549F9)]
+        pub fn reclaim_expired(&mut self, account: AccountId) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            let Some(a) = self.accounts.get(account) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            if a.expires_at == 0 || self.env().block_number() < a.expires_at {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowNotExpired),
+                });
+                return Err(Error::EscrowNotExpired.into());
+            }
+
+            // A frozen account is under review by the manager
+            if a.status == 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountFrozen),
+                });
+                return Err(Error::EscrowAccountFrozen.into());
+            }
+
+            // A disputed account is waiting for the arbiter
+            if a.status == 2 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDisputed),
+                });
+                return Err(Error::EscrowAccountDisputed.into());
+            }
+
+            // Return the funds to the depositor and the other contributors
+            self.refund_contributors(&a)?;
+
+            let reference = a.reference;
+            let amount = a.balance;
+            self.remove_account(&a, Activity::Reclaimed);
+
+            let operation = Success::EscrowAccountRefunded { reference, amount };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Require recipients to confirm their address on accounts added from now on
+        #[ink(message, selector = 0x6639FE47)]
+        pub fn set_recipient_confirmation(&mut self, required: bool) -> Result<(), Error> {
+
+            // Only the owner can change the confirmation requirement
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.recipient_confirmation = required;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Require recipients to accept the accounts added from now on before they
+        /// become active, so no escrow is bound to a recipient that did not agree
+        #[ink(message, selector = 0x3843DDA7)]
+        pub fn set_recipient_acceptance(&mut self, required: bool) -> Result<(), Error> {
+
+            // Only the owner can change the acceptance requirement
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.recipient_acceptance = required;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Accept a pending escrow account, done by its recipient. The account becomes
+        /// active and the recipient address is confirmed.
+        #[ink(message, selector = 0x841195F4)]
+        pub fn accept(&mut self, reference: u16) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+
+            // Locate the pending account by reference, the caller must be its recipient
+            let Some(mut a) = self.account_by_reference(reference)
+                .filter(|a| a.status == 3 && a.recipient.local_account() == Some(caller)) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            a.status = 1;
+            a.confirmed = true;
+            self.save_account(&a);
+            self.record_history(reference, Activity::Accepted);
+
+            let operation = Success::EscrowAccountAccepted { reference };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Cancel a pending escrow account the recipient has not accepted, done by its
+        /// depositor within the cancel window of the default terms. The balance goes
+        /// back to the depositor and the other contributors.
+        #[ink(message, selector = 0x9796E9A7)]
+        pub fn cancel(&mut self, reference: u16) -> Result<(), ContractError> {
+
+            let caller = self.env().caller();
+
+            // Locate the pending account by reference, the caller must be its depositor
+            let Some(a) = self.account_by_reference(reference)
+                .filter(|a| a.status == 3 && a.account == caller) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // A zero cancel window leaves pending accounts cancellable until accepted
+            if self.cancel_window != 0
+                && self.env().block_number() > a.added_at.saturating_add(self.cancel_window) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowCancelWindowClosed),
+                });
+                return Err(Error::EscrowCancelWindowClosed.into());
+            }
+
+            // Return the funds to the depositor and the other contributors
+            self.refund_contributors(&a)?;
+
+            let amount = a.balance;
+            self.remove_account(&a, Activity::Cancelled);
+
+            let operation = Success::EscrowAccountCancelled { reference, amount };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Convert an escrow account whose release failed, e.g. the recipient cannot
+        /// receive the asset, into a refund of the whole balance to the depositor.
+        /// The refund goes through the same approvals as a manager override.
+        #[ink(message, selector = 0x10C0BDA7)]
+        pub fn convert_to_refund(&mut self, account: AccountId) -> Result<(), ContractError> {
+
+            // Redirecting a payout is a manager override, done by a releaser
+            let caller = self.env().caller();
+            if !self.has_role(Role::Releaser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            let Some(a) = self.accounts.get(account) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound.into());
+            };
+
+            // A disputed account is settled by the arbiter
+            if a.status == 2 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDisputed),
+                });
+                return Err(Error::EscrowAccountDisputed.into());
+            }
+
+            // Only an account whose release failed is stuck
+            if !self.release_failures.contains(account) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowReleaseNotFailed),
+                });
+                return Err(Error::EscrowReleaseNotFailed.into());
+            }
+
+            // The depositor receives the whole balance, free of the protocol fee
+            self.propose_or_execute(PendingRelease {
+                manager: caller,
+                account,
+                recipient: account,
+                recipient_bps: 0,
+                amount: None,
+                key: None,
+                initiated_at: self.env().block_number(),
+                refund: true,
+            })
+            .map(|_| ())
+        }
+
+        /// Pause the release of the escrow account of the caller for
+        /// `ACCOUNT_PAUSE_DURATION` blocks, a self-service fraud response while the
+        /// depositor contacts the manager. Pausing again restarts the duration, it does
+        /// not add up.
+        #[ink(message, selector = 0x16447F69)]
+        pub fn pause_my_account(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let Some(mut a) = self.accounts.get(caller) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            let until = self.env().block_number().saturating_add(ACCOUNT_PAUSE_DURATION);
+            a.paused_until = until;
+            self.save_account(&a);
+            let reference = a.reference;
+            self.announce_due(reference, until);
+            self.record_history(reference, Activity::Paused);
+
+            let operation = Success::EscrowAccountPaused { reference, until };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Allow a spender (e.g. a billing contract) to release parts of the escrow
+        /// account of the caller with `release_from`, up to `cap` in total. Approving
+        /// again replaces the remaining allowance.
+        #[ink(message, selector = 0x80EDF1A6)]
+        pub fn approve_spender(&mut self, spender: AccountId, cap: u128) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let Some(a) = self.accounts.get(caller) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            let reference = a.reference;
+            self.spender_allowances.insert((caller, spender), &(reference, cap));
+
+            let operation = Success::EscrowSpenderApproved { reference, spender, cap };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Revoke the allowance of a spender on the escrow account of the caller
+        #[ink(message, selector = 0xCB65BB8A)]
+        pub fn revoke_spender(&mut self, spender: AccountId) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let Some(a) = self.accounts.get(caller) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            let reference = a.reference;
+            self.spender_allowances.remove((caller, spender));
+
+            let operation = Success::EscrowSpenderRevoked { reference, spender };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the remaining allowance of a spender on the escrow account of a
+        /// depositor
+        #[ink(message, selector = 0xB3BAC9BB)]
+        pub fn get_allowance(&self, account: AccountId, spender: AccountId) -> u128 {
+            self.allowance_of(account, spender)
+        }
+
+        /// Remaining allowance of a spender, an allowance granted on an account since
+        /// removed does not carry over to a new account of the same depositor
+        fn allowance_of(&self, account: AccountId, spender: AccountId) -> u128 {
+            match (self.spender_allowances.get((account, spender)), self.accounts.get(account)) {
+                (Some((reference, cap)), Some(a)) if a.reference == reference => cap,
+                _ => 0,
+            }
+        }
+
+        /// Set the recipient of an escrow account, on this chain, as an EVM address or
+        /// on another chain. The depositor can correct its recipient before release,
+        /// the manager can override any recipient. Local and EVM recipients must
+        /// confirm again when confirmation is required, remote recipients are vouched
+        /// for by the manager.
+        #[ink(message, selector = 0xC4A33EA6)]
+        pub fn set_recipient(&mut self, reference: u16, recipient: Recipient) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+
+            let Some(mut a) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            // The manager changes any recipient, the depositor only its own account to
+            // a local or EVM recipient
+            let is_depositor = caller == a.account && recipient.local_account().is_some();
+            if caller != self.manager && !is_depositor {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            // A disputed recipient is left to the arbiter
+            if caller != self.manager && a.status == 2 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountDisputed),
+                });
+                return Err(Error::EscrowAccountDisputed);
+            }
+
+            if self.recipient_blocked(&recipient) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::RecipientBlocked),
+                });
+                return Err(Error::RecipientBlocked);
+            }
+
+            a.confirmed = recipient.local_account().is_none() || !self.recipient_confirmation;
+            let old = core::mem::replace(&mut a.recipient, recipient.clone());
+            self.save_account(&a);
+            if let Some(mut consent) = self.release_consents.get(reference) {
+                consent.recipient = false;
+                self.release_consents.insert(reference, &consent);
+            }
+            self.record_history(reference, Activity::RecipientChanged);
+
+            let operation = Success::EscrowRecipientSet { reference, old, new: recipient };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Confirm the ownership of the recipient address of an escrow account, done
+        /// by the recipient itself
+        #[ink(message, selector = 0x3F2F6B31)]
+        pub fn confirm_recipient(&mut self, reference: u16) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+
+            // Locate the account by reference, the caller must be its recipient
+            let Some(mut a) = self.account_by_reference(reference)
+                .filter(|a| a.recipient.local_account() == Some(caller)) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            a.confirmed = true;
+            self.save_account(&a);
+            self.record_history(reference, Activity::RecipientConfirmed);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowRecipientConfirmed { reference }),
+            });
+            self.emit_account_event(a.correlation_id, reference, Success::EscrowRecipientConfirmed { reference });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Redirect the payouts of the caller, as a recipient, to another address it
+        /// controls. Setting the caller itself clears the redirection.
+        #[ink(message, selector = 0xC8DB9B86)]
+        pub fn set_payout_address(&mut self, new_address: AccountId) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            // Payouts cannot be redirected to a blocked address
+            if new_address != caller && self.blocked_recipients.contains(new_address) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::RecipientBlocked),
+                });
+                return Err(Error::RecipientBlocked);
+            }
+
+            if new_address == caller {
+                self.payout_addresses.remove(caller);
+            } else {
+                self.payout_addresses.insert(caller, &new_address);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowPayoutAddressSet),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the address the payouts of a recipient are sent to
+        #[ink(message, selector = 0x767789A5)]
+        pub fn payout_address(&self, recipient: AccountId) -> AccountId {
+            self.payout_addresses.get(recipient).unwrap_or(recipient)
+        }
+
+        /// Attach a tag to an escrow account
+        #[ink(message, selector = 0xA5D15577)]
+        pub fn add_tag(&mut self, reference: u16, tag: String) -> Result<(), Error> {
+
+            // Tagging can only be done by the manager
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if tag.is_empty() || tag.len() > MAXIMUM_TAG_LENGTH {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowTagInvalid),
+                });
+                return Err(Error::EscrowTagInvalid);
+            }
+
+            let Some(mut a) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            if !a.tags.contains(&tag) {
+                if a.tags.len() >= MAXIMUM_TAGS {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowTagMax),
+                    });
+                    return Err(Error::EscrowTagMax);
+                }
+                a.tags.push(tag);
+                self.save_account(&a);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowTagAdded { reference }),
+            });
+            self.emit_account_event(a.correlation_id, reference, Success::EscrowTagAdded { reference });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Remove a tag from an escrow account
+        #[ink(message, selector = 0x28D2FBA8)]
+        pub fn remove_tag(&mut self, reference: u16, tag: String) -> Result<(), Error> {
+
+            // Untagging can only be done by the manager
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let Some(mut a) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            a.tags.retain(|t| *t != tag);
+            self.save_account(&a);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowTagRemoved { reference }),
+            });
+            self.emit_account_event(a.correlation_id, reference, Success::EscrowTagRemoved { reference });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the correlation id of an escrow account, included as a topic of all
+        /// subsequent account events
+        #[ink(message, selector = 0x3B57A024)]
+        pub fn set_correlation_id(&mut self,
+            reference: u16,
+            correlation_id: Option<Hash>) -> Result<(), Error> {
+
+            // The correlation id is supplied by the dApp through the manager
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let Some(mut a) = self.account_by_reference(reference) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            };
+
+            a.correlation_id = correlation_id;
+            self.save_account(&a);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowCorrelationSet { reference }),
+            });
+            self.emit_account_event(correlation_id, reference, Success::EscrowCorrelationSet { reference });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Emit the account event of an operation if the account carries a
+        /// correlation id
+        fn emit_account_event(&self,
+            correlation_id: Option<Hash>,
+            reference: u16,
+            operation: Success) {
+
+            if let Some(correlation_id) = correlation_id {
+                self.env().emit_event(AccountEvent {
+                    correlation_id,
+                    reference,
+                    status: EscrowStatus::EmitSuccess(operation),
+                });
+            }
+        }
+
+        /// Get the escrow accounts in slot order, paginated, so dApps can show the
+        /// escrow book without replaying events
+        #[ink(message, selector = 0xEF03EAD7)]
+        pub fn get_accounts(&self, offset: u32, limit: u32) -> Vec<Account> {
+            let end = (self.account_count as u32).min(offset.saturating_add(limit));
+            (offset..end)
+                .filter_map(|slot| self.account_slots.get(slot as u16))
+                .filter_map(|account| self.accounts.get(account))
+                .collect()
+        }
+
+        /// Get the number of escrow accounts
+        #[ink(message, selector = 0xF8C9FE4C)]
+        pub fn count_accounts(&self) -> u32 {
+            self.account_count as u32
+        }
+
+        /// Get the escrow account of a depositor
+        #[ink(message, selector = 0xD0F48683)]
+        pub fn get_account(&self, account: AccountId) -> Option<Account> {
+            self.accounts.get(account)
+        }
+
+        /// Get the escrow account of a reference
+        #[ink(message, selector = 0x9C507B81)]
+        pub fn get_by_reference(&self, reference: u16) -> Option<Account> {
+            self.account_by_reference(reference)
+        }
+
+        /// Get the escrow accounts paid to a recipient on this chain, EVM recipients
+        /// are matched through their mapped account
+        #[ink(message, selector = 0xA06B4C99)]
+        pub fn get_by_recipient(&self, recipient: AccountId) -> Vec<Account> {
+            self.account_list()
+                .into_iter()
+                .filter(|a| a.recipient.local_account() == Some(recipient))
+                .collect()
+        }
+
+        /// Get the escrow accounts carrying a tag, paginated
+        #[ink(message, selector = 0xFCFD684E)]
+        pub fn get_accounts_by_tag(&self, tag: String, offset: u32, limit: u32) -> Vec<Account> {
+            self.account_list()
+                .into_iter()
+                .filter(|a| a.tags.contains(&tag))
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect()
+        }
+
+        /// Write an internal note on an escrow account
+        #[ink(message, selector = 0x3493B4F2)]
+        pub fn add_note(&mut self, reference: u16, text: String) -> Result<(), Error> {
+
+            // Notes can only be written by the manager
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if text.is_empty() || text.len() > MAXIMUM_NOTE_LENGTH {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowNoteInvalid),
+                });
+                return Err(Error::EscrowNoteInvalid);
+            }
+
+            if !self.references.contains(reference) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAccountNotFound),
+                });
+                return Err(Error::EscrowAccountNotFound);
+            }
+
+            let mut notes = self.notes.get(reference).unwrap_or_default();
+            if notes.len() >= MAXIMUM_NOTES {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowNoteMax),
+                });
+                return Err(Error::EscrowNoteMax);
+            }
+            notes.push(Note {
+                author: caller,
+                text,
+                block_number: self.env().block_number(),
+            });
+            self.notes.insert(reference, &notes);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowNoteAdded { reference }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the performance counters of a manager, only the owner can query them
+        #[ink(message, selector = 0x22BE3723)]
+        pub fn get_manager_metrics(&self, manager: AccountId) -> Result<ManagerMetrics, Error> {
+            if self.env().caller() != self.owner {
+                return Err(Error::BadOrigin);
+            }
+
+            Ok(self.manager_metrics.get(manager).unwrap_or_default())
+        }
+
+        /// Count a release approved by a manager and the blocks it waited since the add
+        fn record_approval(&mut self, manager: AccountId, added_at: BlockNumber) {
+            let mut metrics = self.manager_metrics.get(manager).unwrap_or_default();
+            let waited = self.env().block_number().saturating_sub(added_at);
+            metrics.releases_approved = metrics.releases_approved.saturating_add(1);
+            metrics.approval_blocks = metrics.approval_blocks.saturating_add(waited);
+            metrics.average_approval_blocks = metrics.approval_blocks / metrics.releases_approved;
+            self.manager_metrics.insert(manager, &metrics);
+        }
+
+        /// Get the internal notes of an escrow account, only auditors and the owner
+        /// can query them
+        #[ink(message, selector = 0xBC552132)]
+        pub fn get_notes(&self, reference: u16) -> Result<Vec<Note>, Error> {
+            let caller = self.env().caller();
+            if !self.has_role(Role::Auditor, caller) && caller != self.owner {
+                return Err(Error::BadOrigin);
+            }
+
+            Ok(self.notes.get(reference).unwrap_or_default())
+        }
+
+        /// Grant a role to an account
+        #[ink(message, selector = 0x2AABFAB5)]
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<(), Error> {
+
+            // Only the owner grants roles
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.roles.insert((role, account), &());
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowRoleGranted { role, account }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Revoke a role from an account, the manager keeps every role
+        #[ink(message, selector = 0x35E1EF4A)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<(), Error> {
+
+            // Only the owner revokes roles
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.roles.remove((role, account));
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowRoleRevoked { role, account }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Check if an account holds a role, the manager holds every role but the
+        /// arbiter role, so it never rules on disputes over its own book, and the
+        /// appointed auditor the auditor role
+        #[ink(message, selector = 0x8D194A68)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            (account == self.manager && role != Role::Arbiter)
+                || (role == Role::Auditor && account == self.auditor)
+                || self.roles.contains((role, account))
+        }
+
+        /// Set the auditor allowed to read the internal notes
+        #[ink(message, selector = 0xE31010BE)]
+        pub fn set_auditor(&mut self, auditor: AccountId) -> Result<(), Error> {
+
+            // Only the owner appoints the auditor
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.auditor = auditor;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Nominate a new owner, the ownership only changes once the nominee calls
+        /// `accept_ownership`. Nominating again replaces the pending nominee.
+        #[ink(message, selector = 0x107E33EA)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), Error> {
+
+            // Only the owner can hand over the ownership
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.pending_owner = Some(new_owner);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowOwnerNominated { owner: new_owner }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Accept the ownership nominated by `transfer_ownership`
+        #[ink(message, selector = 0xB55BE9F0)]
+        pub fn accept_ownership(&mut self) -> Result<(), Error> {
+
+            // Only the nominee can accept, proving it controls the new key
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.owner = caller;
+            self.pending_owner = None;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowOwnerChanged { owner: caller }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Nominate a new manager without touching the escrow accounts, the manager
+        /// only changes once the nominee calls `accept_manager`. Nominating again
+        /// replaces the pending nominee.
+        #[ink(message, selector = 0x2C7DD747)]
+        pub fn set_manager(&mut self, new_manager: AccountId) -> Result<(), Error> {
+
+            // Only the owner appoints the manager
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.pending_manager = Some(new_manager);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowManagerNominated { manager: new_manager }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Accept the manager role nominated by `set_manager`
+        #[ink(message, selector = 0xE96D1153)]
+        pub fn accept_manager(&mut self) -> Result<(), Error> {
+
+            // Only the nominee can accept, proving it controls the new key
+            let caller = self.env().caller();
+            if self.pending_manager != Some(caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.manager = caller;
+            self.pending_manager = None;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowManagerChanged { manager: caller }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Save a payout template, updating it affects every account referencing it
+        /// that is not yet released
+        #[ink(message, selector = 0x441C77A1)]
+        pub fn set_template(&mut self,
+            id: u16,
+            name: String,
+            shares: Vec<PayoutShare>) -> Result<(), Error> {
+
+            // Templates can only be managed by the owner
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            // Shares must be bounded and add up to 100%
+            let total: u32 = shares.iter().map(|s| s.bps as u32).sum();
+            if shares.is_empty() || shares.len() > MAXIMUM_TEMPLATE_SHARES || total != 10_000 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowTemplateInvalid),
+                });
+                return Err(Error::EscrowTemplateInvalid);
+            }
+
+            self.templates.insert(id, &PayoutTemplate { name, shares });
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowTemplateSaved { id }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Remove a payout template, accounts referencing it cannot be released until
+        /// it is saved again
+        #[ink(message, selector = 0x480C5A63)]
+        pub fn remove_template(&mut self, id: u16) -> Result<(), Error> {
+
+            // Templates can only be managed by the owner
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.templates.remove(id);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowTemplateRemoved { id }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get a payout template
+        #[ink(message, selector = 0xF3A147CD)]
+        pub fn get_template(&self, id: u16) -> Option<PayoutTemplate> {
+            self.templates.get(id)
+        }
+
+        /// Mint new escrow accounts as PSP34 position NFTs
+        #[ink(message, selector = 0x8AC6F413)]
+        pub fn set_nft_positions(&mut self, enabled: bool) -> Result<(), Error> {
+
+            // Only the owner can change the position representation
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.nft_positions = enabled;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the escrow account of a reference
+        fn account_by_reference(&self, reference: u16) -> Option<Account> {
+            self.references.get(reference).and_then(|account| self.accounts.get(account))
+        }
+
+        /// Get all escrow accounts in slot order, reserved for queries and checks that
+        /// must see the whole book
+        fn account_list(&self) -> Vec<Account> {
+            (0..self.account_count)
+                .filter_map(|slot| self.account_slots.get(slot))
+                .filter_map(|account| self.accounts.get(account))
+                .collect()
+        }
+
+        /// Insert a new escrow account with its reference index and slot
+        fn insert_account(&mut self, account: &Account) {
+            self.accounts.insert(account.account, account);
+            self.references.insert(account.reference, &account.account);
+            self.account_slots.insert(self.account_count, &account.account);
+            self.slot_of.insert(account.account, &self.account_count);
+            self.account_count += 1;
+            self.credit(account.asset_id, account.balance);
+
+            self.env().emit_event(AccountAdded {
+                account: account.account,
+                recipient: account.recipient.clone(),
+                reference: account.reference,
+                asset_id: account.asset_id,
+                amount: account.balance,
+            });
+        }
+
+        /// Write back an updated escrow account, balance changes must also be applied
+        /// through `credit` / `debit`
+        fn save_account(&mut self, account: &Account) {
+            self.accounts.insert(account.account, account);
+        }
+
+        /// Add to the running account balance of an asset, `total_balance` for the
+        /// escrow asset
+        fn credit(&mut self, asset_id: u128, amount: u128) {
+            if asset_id == self.asset_id {
+                self.total_balance = self.total_balance.saturating_add(amount);
+            } else {
+                let balance = self.asset_balances.get(asset_id).unwrap_or(0);
+                self.asset_balances.insert(asset_id, &balance.saturating_add(amount));
+            }
+        }
+
+        /// Subtract from the running account balance of an asset
+        fn debit(&mut self, asset_id: u128, amount: u128) {
+            if asset_id == self.asset_id {
+                self.total_balance = self.total_balance.saturating_sub(amount);
+            } else {
+                let balance = self.asset_balances.get(asset_id).unwrap_or(0).saturating_sub(amount);
+                if balance == 0 {
+                    self.asset_balances.remove(asset_id);
+                } else {
+                    self.asset_balances.insert(asset_id, &balance);
+                }
+            }
+        }
+
+        /// Remove an escrow account (gas efficient) and clean up its position NFT and
+        /// contributions
+        fn remove_account(&mut self, account: &Account, activity: Activity) {
+            let reference = account.reference;
+            self.burn_position(account);
+            self.contributions.remove(reference);
+            self.notes.remove(reference);
+            self.notified_due.remove(reference);
+            self.vestings.remove(reference);
+            self.release_consents.remove(reference);
+            self.release_failures.remove(account.account);
+            self.unlink_account(account);
+            self.debit(account.asset_id, account.balance);
+            if matches!(activity, Activity::Released | Activity::ForceReleased) {
+                self.record_release(account, account.balance, &activity);
+            }
+            self.record_history(reference, activity);
+        }
+
+        /// Add a released amount to the running total of the escrow asset, releases
+        /// to the recipient emit `Released`, the overrides emit their own event
+        fn record_release(&mut self, account: &Account, amount: u128, activity: &Activity) {
+            if account.asset_id == self.asset_id {
+                self.total_released = self.total_released.saturating_add(amount);
+            }
+
+            if *activity == Activity::Released {
+                self.env().emit_event(Released {
+                    account: account.account,
+                    recipient: account.recipient.clone(),
+                    reference: account.reference,
+                    amount,
+                });
+            }
+        }
+
+        /// Move an escrow account to another depositor, keeping its slot
+        fn move_account(&mut self, account: &mut Account, to: AccountId) {
+            let slot = self.slot_of.take(account.account).unwrap_or_default();
+            self.accounts.remove(account.account);
+            account.account = to;
+            self.accounts.insert(to, account);
+            self.references.insert(account.reference, &to);
+            self.account_slots.insert(slot, &to);
+            self.slot_of.insert(to, &slot);
+        }
+
+        /// Drop an escrow account from the storage and its indexes, the last slot is
+        /// swapped into the freed one
+        fn unlink_account(&mut self, account: &Account) {
+            let slot = self.slot_of.take(account.account).unwrap_or_default();
+            self.accounts.remove(account.account);
+            self.references.remove(account.reference);
+
+            self.account_count -= 1;
+            let last = self.account_slots.take(self.account_count);
+            if let Some(last) = last.filter(|_| slot != self.account_count) {
+                self.account_slots.insert(slot, &last);
+                self.slot_of.insert(last, &slot);
+            }
+        }
+
+        /// Delete all escrow accounts, used by `setup`
+        fn clear_accounts(&mut self) {
+            for slot in 0..self.account_count {
+                if let Some(account) = self.account_slots.take(slot) {
+                    if let Some(a) = self.accounts.take(account) {
+                        self.references.remove(a.reference);
+                        self.contributions.remove(a.reference);
+                        self.notes.remove(a.reference);
+                        self.notified_due.remove(a.reference);
+                        self.asset_balances.remove(a.asset_id);
+                    }
+                    self.slot_of.remove(account);
+                }
+            }
+            self.account_count = 0;
+            self.total_balance = 0;
+        }
+
+        /// Locate the escrow account of a position NFT
+        fn position_of(&self, id: &Id) -> Option<Account> {
+            match id {
+                Id::U16(reference) => self.account_by_reference(*reference).filter(|a| a.nft),
+                _ => None,
+            }
+        }
+
+        /// Burn the position NFT of an escrow account that is about to be removed
+        fn burn_position(&self, account: &Account) {
+            if account.nft {
+                self.env().emit_event(Transfer {
+                    from: Some(account.account),
+                    to: None,
+                    id: Id::U16(account.reference),
+                });
+            }
+        }
+
+        /// Verify the bookkeeping invariants at the end of state-changing messages,
+        /// enabled with the `debug-assertions` feature
+        #[cfg(feature = "debug-assertions")]
+        fn assert_invariants(&self) {
+            assert!(self.status <= 1, "invalid escrow status");
+            assert!(self.close_policy <= 2, "invalid close policy");
+
+            let mut total_balance = 0u128;
+            for slot in 0..self.account_count {
+                // Every slot holds an escrow account reachable through its indexes
+                let account = self.account_slots.get(slot).expect("empty account slot");
+                let a = self.accounts.get(account).expect("dangling account slot");
+                assert_eq!(self.slot_of.get(account), Some(slot), "slot index mismatch");
+                assert_eq!(self.references.get(a.reference), Some(account), "reference index mismatch");
+                if a.asset_id == self.asset_id {
+                    total_balance = total_balance.saturating_add(a.balance);
+                }
+
+                // Third-party contributions are covered by the balance
+                let funded = self.contributions
+                    .get(a.reference)
+                    .unwrap_or_default()
+                    .iter()
+                    .fold(0u128, |total, c| total.saturating_add(c.amount));
+                assert!(funded <= a.balance, "contributions exceed balance");
+            }
+            assert_eq!(total_balance, self.total_balance, "total balance mismatch");
+
+            // The queued amount matches the queued escrow asset transfers
+            let queued = (self.queue_head..self.queue_tail)
+                .filter_map(|n| self.transfer_queue.get(n))
+                .filter(|p| p.asset_id == self.asset_id)
+                .fold(0u128, |total, p| total.saturating_add(p.amount));
+            assert_eq!(queued, self.queued_amount, "queued amount mismatch");
+        }
+
+        #[cfg(not(feature = "debug-assertions"))]
+        fn assert_invariants(&self) {}
+
+        /// Register the escrow with a companion registry
+        #[ink(message, selector = 0xA55C4B84)]
+        pub fn set_registry(&mut self, registry: AccountId) -> Result<(), Error> {
+
+            // Only the owner decides where the escrow is listed
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if let Err(error) = self.register_with(registry) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(error.clone()),
+                });
+                return Err(error);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowRegistryUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Report the liveness and status of the escrow to its registry, callable by
+        /// anyone
+        #[ink(message, selector = 0xAC442764)]
+        pub fn heartbeat(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let Some(registry) = self.registry else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowRegistryNotSet),
+                });
+                return Err(Error::EscrowRegistryNotSet);
+            };
+
+            let mut registry: ink::contract_ref!(EscrowRegistry) = registry.into();
+            if registry.heartbeat(self.status).is_err() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowRegistryRejected),
+                });
+                return Err(Error::EscrowRegistryRejected);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowRegistryUpdated),
+            });
+
+            Ok(())
+        }
+
+        /// Register with a registry, which reads the code hash of the escrow
+        fn register_with(&mut self, registry: AccountId) -> Result<(), Error> {
+            let mut registry_ref: ink::contract_ref!(EscrowRegistry) = registry.into();
+            registry_ref
+                .register(self.asset_id, self.owner)
+                .map_err(|_| Error::EscrowRegistryRejected)?;
+            self.registry = Some(registry);
+            Ok(())
+        }
+
+        /// Get the feature groups enabled at instantiation (`FEATURE_*` bitflags)
+        #[ink(message, selector = 0xBA5D500F)]
+        pub fn features(&self) -> u32 {
+            self.features
+        }
+
+        /// Check if a feature group is enabled
+        fn feature_enabled(&self, feature: u32) -> bool {
+            self.features & feature == feature
+        }
+
+        /// Check if the escrow holds its maximum number of accounts, a maximum of 0
+        /// means unlimited
+        fn at_capacity(&self) -> bool {
+            self.maximum_accounts != 0 && self.account_count >= self.maximum_accounts
+        }
+
+        /// Check if adding accounts is blocked by the escrow status
+        fn adds_blocked(&self) -> bool {
+            self.status != 0 && self.close_policy != 2
+        }
+
+        /// Check if releasing accounts is blocked by the escrow status
+        fn releases_blocked(&self) -> bool {
+            self.status != 0 && self.close_policy != 1
+        }
+
+        /// Check if a recipient on this chain is blocked, remote recipients cannot be
+        fn recipient_blocked(&self, recipient: &Recipient) -> bool {
+            recipient
+                .local_account()
+                .is_some_and(|account| self.account_blocked(account))
+        }
+
+        /// Check if an account or the payout address it redirects to is blocked
+        fn account_blocked(&self, account: AccountId) -> bool {
+            self.blocked_recipients.contains(account)
+                || self.blocked_recipients.contains(self.payout_address(account))
+        }
+
+        /// Block a recipient, e.g. a sanctioned address, or unblock it. Funds already
+        /// escrowed for it stay until it is unblocked or the account is refunded.
+        #[ink(message, selector = 0xECB1AD02)]
+        pub fn set_recipient_blocked(&mut self, recipient: AccountId, blocked: bool) -> Result<(), Error> {
+
+            // Only the owner manages the blocked recipients
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if blocked {
+                self.blocked_recipients.insert(recipient, &());
+            } else {
+                self.blocked_recipients.remove(recipient);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowRecipientBlockUpdated { recipient, blocked }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Check if a recipient is blocked
+        #[ink(message, selector = 0xAD43A57F)]
+        pub fn is_recipient_blocked(&self, recipient: AccountId) -> bool {
+            self.blocked_recipients.contains(recipient)
+        }
+
+        /// Check if a depositor may enter the escrow, anyone may while the allow-list
+        /// is disabled
+        fn depositor_allowed(&self, account: AccountId) -> bool {
+            !self.allow_list_enabled || self.allow_list.get(account).unwrap_or(false)
+        }
+
+        /// Enable or disable the depositor allow-list
+        #[ink(message, selector = 0xC09ACF4C)]
+        pub fn set_allow_list_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+
+            // Only the manager manages the allow-list
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.allow_list_enabled = enabled;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Add a depositor to the allow-list or remove it
+        #[ink(message, selector = 0xDD41E154)]
+        pub fn set_allowed(&mut self, account: AccountId, allowed: bool) -> Result<(), Error> {
+
+            // Only the manager manages the allow-list
+            let caller = self.env().caller();
+            if caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if allowed {
+                self.allow_list.insert(account, &true);
+            } else {
+                self.allow_list.remove(account);
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowAllowListUpdated { account, allowed }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Check if a depositor may enter the escrow, always true while the allow-list
+        /// is disabled
+        #[ink(message, selector = 0xB3151833)]
+        pub fn is_allowed(&self, account: AccountId) -> bool {
+            self.depositor_allowed(account)
+        }
+
+        /// Check if an operation is paused by its pause switch
+        fn is_paused(&self, flag: u8) -> bool {
+            self.paused & flag != 0
+        }
+
+        /// Set the pause switches (`PAUSE_*` bitflags) halting single operations,
+        /// e.g. new deposits while recipients can still withdraw, apart from the
+        /// open/close status
+        #[ink(message, selector = 0x4B7659E5)]
+        pub fn set_paused(&mut self, flags: u8) -> Result<(), Error> {
+
+            // Pausing can only be done by a pauser
+            let caller = self.env().caller();
+            if !self.has_role(Role::Pauser, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.paused = flags & PAUSE_ALL;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowPauseUpdated { flags: self.paused }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the pause switches (`PAUSE_*` bitflags)
+        #[ink(message, selector = 0x9D38DB8B)]
+        pub fn get_paused(&self) -> u8 {
+            self.paused
+        }
+
+        /// Rescue assets mistakenly sent to the escrow, the escrow asset itself covers
+        /// the liabilities and the manager bond and can never be rescued
+        #[ink(message, selector = 0xC9E04468)]
+        pub fn rescue_asset(&mut self,
+            asset_id: u128,
+            amount: u128,
+            to: AccountId) -> Result<(), ContractError> {
+
+            // Rescue can only be done by the owner
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            if asset_id == self.asset_id || self.asset_balances.contains(asset_id) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowAssetProtected),
+                });
+                return Err(Error::EscrowAssetProtected.into());
+            }
+
+            self.transfer(asset_id, to, amount, None)?;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowAssetRescued { asset_id, amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the native balance of the escrow (balance, storage funds, excess), the
+        /// excess is the balance beyond the existential deposit, the storage funds and
+        /// on a native escrow the liabilities
+        #[ink(message, selector = 0x524918AC)]
+        pub fn get_native_balance(&self) -> (Balance, Balance, Balance) {
+            (
+                self.env().balance(),
+                self.storage_funds,
+                self.native_excess(),
+            )
+        }
+
+        /// Native balance that can be withdrawn, a native escrow keeps what it owes
+        fn native_excess(&self) -> Balance {
+            let owed = if self.backend == TransferBackend::Native {
+                self.liabilities()
+            } else {
+                0
+            };
+            self.env()
+                .balance()
+                .saturating_sub(self.env().minimum_balance())
+                .saturating_sub(self.storage_funds)
+                .saturating_sub(owed)
+        }
+
+        /// Set the minimum balance of the escrow asset and what happens to account
+        /// transfers below it (0 = transfer anyway, 1 = keep in the dust pot)
+        #[ink(message, selector = 0x0ECF231B)]
+        pub fn set_dust_policy(&mut self, threshold: u128, policy: u8) -> Result<(), Error> {
+
+            // Only the owner can change the dust policy
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.dust_threshold = threshold;
+            self.dust_policy = policy.min(1);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Withdraw the dust pot
+        #[ink(message, selector = 0x12457FA4)]
+        pub fn withdraw_dust(&mut self, to: AccountId) -> Result<(), ContractError> {
+
+            // Only the owner can withdraw the dust
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            let amount = self.dust_pot;
+            self.dust_pot = 0;
+            self.transfer(self.asset_id, to, amount, None)?;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowDustWithdrawn { amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Withdraw excess native balance held by the escrow
+        #[ink(message, selector = 0xC3F16695)]
+        pub fn withdraw_native(&mut self,
+            amount: Balance,
+            to: AccountId) -> Result<(), ContractError> {
+
+            // Withdrawal can only be done by the owner
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            if amount > self.native_excess() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowNativeInsufficient),
+                });
+                return Err(Error::EscrowNativeInsufficient.into());
+            }
+
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| RuntimeError::NativeTransferFailed)?;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowNativeWithdrawn { amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Pre-fund future storage growth with the transferred native value
+        #[ink(message, payable, selector = 0xCED10CDF)]
+        pub fn fund_storage(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let amount = self.env().transferred_value();
+            self.storage_funds = self.storage_funds.saturating_add(amount);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowStorageFunded { amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Queue transfers for later execution through `flush` instead of executing
+        /// them right away, `throughput` is the transfers expected to be flushed per
+        /// block
+        #[ink(message, selector = 0x1F41E04D)]
+        pub fn set_deferred_transfers(&mut self, enabled: bool, throughput: u32) -> Result<(), Error> {
+
+            // Only the owner can change how transfers are dispatched
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.deferred_transfers = enabled;
+            self.queue_throughput = throughput.max(1);
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Execute up to `limit` queued transfers, callable by anyone. A failed transfer
+        /// is moved to the tail of the queue to be retried by a later flush.
+        #[ink(message, selector = 0xAA3D6F5B)]
+        pub fn flush(&mut self, limit: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut processed = 0u32;
+            while processed < limit && self.queue_head != self.queue_tail {
+                let head = self.queue_head;
+                self.queue_head = head.wrapping_add(1);
+                processed += 1;
+
+                let Some(mut pending) = self.transfer_queue.take(head) else {
+                    continue;
+                };
+
+                let result = self.send(pending.asset_id, pending.target, pending.amount);
+
+                if pending.asset_id == self.asset_id {
+                    self.queued_amount = self.queued_amount.saturating_sub(pending.amount);
+                }
+
+                if let Err(error) = result {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowTransferFailed),
+                    });
+                    self.env().emit_event(TransferFailed {
+                        target: pending.target,
+                        asset_id: pending.asset_id,
+                        amount: pending.amount,
+                        error,
+                    });
+                    pending.attempts = pending.attempts.saturating_add(1);
+                    self.enqueue_transfer(pending);
+                }
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowQueueFlushed { processed }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Execute up to `limit` queued transfers as one settlement run, callable by
+        /// anyone. Transfers of the same asset to the same target are summed into a
+        /// single `Assets::transfer` and all transfers are dispatched in one
+        /// `Utility::batch_all`, PSP22 token transfers are sent one by one. The
+        /// transfers of a failed batch or token transfer move to the tail of the queue
+        /// to be retried.
+        #[ink(message, selector = 0x6C337222)]
+        pub fn settle(&mut self, limit: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let mut run = Vec::new();
+            let mut processed = 0u32;
+            while processed < limit && self.queue_head != self.queue_tail {
+                let head = self.queue_head;
+                self.queue_head = head.wrapping_add(1);
+                processed += 1;
+
+                if let Some(pending) = self.transfer_queue.take(head) {
+                    if pending.asset_id == self.asset_id {
+                        self.queued_amount = self.queued_amount.saturating_sub(pending.amount);
+                    }
+                    run.push(pending);
+                }
+            }
+
+            // PSP22 transfers are contract calls sent one by one, only `pallet_assets`
+            // and native transfers fit in the utility batch. Only the transfers of a
+            // failed (asset, target) pair are re-queued.
+            let mut calls = Vec::new();
+            let mut batched = Vec::new();
+            let mut failed = Vec::new();
+            for (id, target, amount) in Self::aggregate_transfers(&run) {
+                let call = match self.backend_of(id) {
+                    TransferBackend::Assets => self.assets_transfer(id, target, amount),
+                    TransferBackend::Native => RuntimeCall::Balances(BalancesCall::Transfer {
+                        dest: target.into(),
+                        value: amount,
+                    }),
+                    TransferBackend::Psp22(_) => {
+                        if self.send(id, target, amount).is_err() {
+                            failed.push((id, target));
+                        }
+                        continue;
+                    }
+                };
+                batched.push((id, target));
+                calls.push(call);
+            }
+            if !calls.is_empty()
+                && self.dispatch(&RuntimeCall::Utility(UtilityCall::BatchAll { calls })).is_err() {
+                failed.append(&mut batched);
+            }
+
+            if !failed.is_empty() {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowTransferFailed),
+                });
+            }
+            for mut pending in run {
+                if failed.contains(&(pending.asset_id, pending.target)) {
+                    pending.attempts = pending.attempts.saturating_add(1);
+                    self.enqueue_transfer(pending);
+                } else if let Some(reference) = pending.reference {
+                    self.env().emit_event(TransferSettled {
+                        reference,
+                        target: pending.target,
+                        amount: pending.amount,
+                    });
+                }
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowQueueFlushed { processed }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Sum queued transfers by asset and target, in order of first appearance
+        fn aggregate_transfers(run: &[PendingTransfer]) -> Vec<(u128, AccountId, u128)> {
+            let mut totals: Vec<(u128, AccountId, u128)> = Vec::new();
+            for pending in run {
+                match totals.iter_mut().find(|t| t.0 == pending.asset_id && t.1 == pending.target) {
+                    Some(total) => total.2 = total.2.saturating_add(pending.amount),
+                    None => totals.push((pending.asset_id, pending.target, pending.amount)),
+                }
+            }
+            totals
+        }
+
+        /// Get the queued transfers, paginated from the head of the queue
+        #[ink(message, selector = 0xE985F50D)]
+        pub fn get_queued_transfers(&self, offset: u32, limit: u32) -> Vec<PendingTransfer> {
+            let length = self.queue_tail.wrapping_sub(self.queue_head);
+            (offset..length.min(offset.saturating_add(limit)))
+                .filter_map(|n| self.transfer_queue.get(self.queue_head.wrapping_add(n)))
+                .collect()
+        }
+
+        /// Get the position from the head of the first queued transfer of a reference
+        /// and the block it is estimated to be flushed at
+        #[ink(message, selector = 0xD9E3C999)]
+        pub fn queue_status(&self, reference: u16) -> Option<(u32, BlockNumber)> {
+            let length = self.queue_tail.wrapping_sub(self.queue_head);
+            let position = (0..length).find(|n| {
+                self.transfer_queue
+                    .get(self.queue_head.wrapping_add(*n))
+                    .is_some_and(|pending| pending.reference == Some(reference))
+            })?;
+
+            let blocks = position / self.queue_throughput.max(1);
+            Some((position, self.env().block_number().saturating_add(blocks)))
+        }
+
+        /// Get the receipt of an idempotency key the operator already used for the
+        /// operation, a replay emits the original receipt operation
+        fn replayed(&self, operator: AccountId, operation: KeyedOperation, key: Option<Hash>) -> Option<Receipt> {
+            let receipt = self.receipts.get((operator, operation, key?))?;
+
+            self.env().emit_event(EscrowEvent {
+                operator,
+                status: EscrowStatus::EmitSuccess(receipt.operation.clone()),
+            });
+
+            Some(receipt)
+        }
+
+        /// Record the receipt of a successful operation under the idempotency key of
+        /// its operator, returns the receipt
+        #[allow(clippy::too_many_arguments)]
+        fn record_receipt(&mut self,
+            operator: AccountId,
+            keyed: KeyedOperation,
+            key: Option<Hash>,
+            operation: Success,
+            reference: u16,
+            account: AccountId,
+            amount: u128) -> Option<Receipt> {
+
+            let receipt = Receipt {
+                operation,
+                reference,
+                account,
+                amount,
+                block_number: self.env().block_number(),
+            };
+            self.receipts.insert((operator, keyed, key?), &receipt);
+
+            Some(receipt)
+        }
+
+        /// Get the receipt of an operation by its operator and idempotency key
+        #[ink(message, selector = 0x137A03B0)]
+        pub fn get_receipt(&self, operator: AccountId, operation: KeyedOperation, key: Hash) -> Option<Receipt> {
+            self.receipts.get((operator, operation, key))
+        }
+
+        /// Get the receipt of an attested release by its document hash
+        #[ink(message, selector = 0x7546D261)]
+        pub fn get_attestation(&self, doc_hash: Hash) -> Option<Receipt> {
+            self.attestations.get(doc_hash)
+        }
+
+        /// Escrow asset owed by the contract (accounts, queued transfers, manager bond,
+        /// dust pot and dispute bonds)
+        fn liabilities(&self) -> u128 {
+            self.total_balance
+                .saturating_add(self.queued_amount)
+                .saturating_add(self.bond)
+                .saturating_add(self.dust_pot)
+                .saturating_add(self.dispute_bonds)
+        }
+
+        /// Get the amount of an asset owed by the contract. The escrow asset covers the
+        /// accounts, queued transfers, manager bond, dust pot and dispute bonds, any
+        /// other asset its accounts and queued transfers (e.g. rescues), so accounting
+        /// never mixes assets.
+        #[ink(message, selector = 0x7E89F85E)]
+        pub fn liability_of(&self, asset_id: u128) -> u128 {
+            if asset_id == self.asset_id {
+                return self.liabilities();
+            }
+
+            let length = self.queue_tail.wrapping_sub(self.queue_head);
+            (0..length)
+                .filter_map(|n| self.transfer_queue.get(self.queue_head.wrapping_add(n)))
+                .filter(|p| p.asset_id == asset_id)
+                .fold(self.asset_balances.get(asset_id).unwrap_or(0), |total, p| total.saturating_add(p.amount))
+        }
+
+        /// Compare the liabilities with the escrow asset balance read from the backend,
+        /// callable by anyone so auditors can check the invariant on-chain. Emits
+        /// `Insolvent` or `Solvent`, an insolvency pauses releases like
+        /// `check_solvency` and adds when configured.
+        #[ink(message, selector = 0x80EF94FF)]
+        pub fn reconcile(&mut self) -> Result<(), Error> {
+
+            let caller = self.env().caller();
+
+            let Some(funds) = self.escrow_funds(self.asset_id) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowBalanceUnavailable),
+                });
+                return Err(Error::EscrowBalanceUnavailable);
+            };
+
+            let liabilities = self.liabilities();
+            if funds >= liabilities {
+                self.env().emit_event(Solvent {
+                    operator: caller,
+                    surplus: funds - liabilities,
+                });
+                return Ok(());
+            }
+
+            self.env().emit_event(Insolvent {
+                operator: caller,
+                deficit: liabilities - funds,
+            });
+
+            // Releases are paused until the owner acknowledges the insolvency
+            self.detect_insolvency(caller, funds);
+
+            // No new deposits join an escrow that cannot cover the current ones
+            if self.reconcile_pauses_adds && !self.is_paused(PAUSE_ADD) {
+                self.paused |= PAUSE_ADD;
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitSuccess(Success::EscrowPauseUpdated { flags: self.paused }),
+                });
+            }
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Let `reconcile` pause adds when it finds the escrow insolvent
+        #[ink(message, selector = 0x2604069F)]
+        pub fn set_reconcile_pauses_adds(&mut self, enabled: bool) -> Result<(), Error> {
+
+            // Only the owner can change the reconciliation policy
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.reconcile_pauses_adds = enabled;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Check the liabilities against the escrow asset balance of the contract read
+        /// from its backend, releases are paused when insolvent
+        #[ink(message, selector = 0xBEC108C5)]
+        pub fn check_solvency(&mut self) -> Result<(), Error> {
+
+            // Only the owner or the manager can pause releases on a solvency check
+            let caller = self.env().caller();
+            if caller != self.owner && caller != self.manager {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let Some(asset_balance) = self.escrow_funds(self.asset_id) else {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowBalanceUnavailable),
+                });
+                return Err(Error::EscrowBalanceUnavailable);
+            };
+
+            self.detect_insolvency(caller, asset_balance);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Pause releases and emit `InsolvencyDetected` when the liabilities exceed the
+        /// escrow asset balance, returns true when insolvent
+        fn detect_insolvency(&mut self, operator: AccountId, asset_balance: u128) -> bool {
+            let liabilities = self.liabilities();
+            if liabilities <= asset_balance {
+                self.env().emit_event(EscrowEvent {
+                    operator,
+                    status: EscrowStatus::EmitSuccess(Success::EscrowSolvent),
+                });
+                return false;
+            }
+
+            self.insolvency_paused = true;
+
+            self.env().emit_event(InsolvencyDetected {
+                operator,
+                liabilities,
+                balance: asset_balance,
+            });
+
+            true
+        }
+
+        /// Resume releases after the insolvency has been remediated
+        #[ink(message, selector = 0xE393B81B)]
+        pub fn acknowledge_and_resume(&mut self) -> Result<(), Error> {
+
+            // Only the owner can resume releases
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.insolvency_paused = false;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowResumed),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the bond a dispute raiser must post
+        #[ink(message, selector = 0x0B97AC5B)]
+        pub fn set_dispute_bond(&mut self, amount: u128) -> Result<(), Error> {
+
+            // Only the owner can change the dispute bond
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if !self.feature_enabled(FEATURE_DISPUTES) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeatureDisabled),
+                });
+                return Err(Error::EscrowFeatureDisabled);
+            }
+
+            self.dispute_bond = amount;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the protocol fee taken off releases and the account collecting it
+        #[ink(message, selector = 0x715ECE73)]
+        pub fn set_fee(&mut self, fee_bps: u16, collector: AccountId) -> Result<(), Error> {
+
+            // Only the owner can change the protocol fee
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if !self.feature_enabled(FEATURE_FEES) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeatureDisabled),
+                });
+                return Err(Error::EscrowFeatureDisabled);
+            }
+
+            if fee_bps > 10_000 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowFeeInvalid),
+                });
+                return Err(Error::EscrowFeeInvalid);
+            }
+
+            self.fee_bps = fee_bps;
+            self.fee_collector = collector;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the protocol fee (fee bps, fee collector)
+        #[ink(message, selector = 0x0BE94636)]
+        pub fn get_fee(&self) -> (u16, AccountId) {
+            (self.fee_bps, self.fee_collector)
+        }
+
+        /// Propose to replace the contract code, the upgrade can be executed once the
+        /// upgrade delay has passed so users can exit before new code runs. A new
+        /// proposal replaces the pending one and restarts the delay.
+        #[ink(message, selector = 0xC00B1291)]
+        pub fn propose_upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+
+            // Only the owner can upgrade the contract
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let execute_at = self.env().block_number().saturating_add(self.upgrade_delay);
+            self.pending_upgrade = Some((code_hash, execute_at));
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowUpgradeProposed { code_hash, execute_at }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Replace the contract code with the proposed code hash once the upgrade
+        /// delay has passed, the storage is kept
+        #[ink(message, selector = 0x9852F7B0)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let result = match self.pending_upgrade {
+                _ if caller != self.owner => Err(Error::BadOrigin),
+                Some((proposed, _)) if proposed != code_hash => Err(Error::EscrowUpgradeNotProposed),
+                None => Err(Error::EscrowUpgradeNotProposed),
+                Some((_, execute_at)) if self.env().block_number() < execute_at => Err(Error::EscrowUpgradeLocked),
+                Some(_) => self.env()
+                    .set_code_hash(&code_hash)
+                    .map_err(|_| Error::EscrowUpgradeFailed),
+            };
+            if let Err(error) = result {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(error.clone()),
+                });
+                return Err(error);
+            }
+            self.pending_upgrade = None;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowUpgraded { code_hash }),
+            });
+
+            Ok(())
+        }
+
+        /// Cancel the proposed code upgrade
+        #[ink(message, selector = 0xCAD3936E)]
+        pub fn cancel_upgrade(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let error = match self.pending_upgrade {
+                _ if caller != self.owner => Some(Error::BadOrigin),
+                None => Some(Error::EscrowUpgradeNotProposed),
+                Some(_) => None,
+            };
+            if let Some(error) = error {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(error.clone()),
+                });
+                return Err(error);
+            }
+            self.pending_upgrade = None;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowUpgradeCancelled),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Set the blocks between an upgrade proposal and its execution, the delay can
+        /// only grow so users can rely on the exit time it gives them
+        #[ink(message, selector = 0x50CDD595)]
+        pub fn set_upgrade_delay(&mut self, delay: BlockNumber) -> Result<(), Error> {
+
+            // Only the owner can change the upgrade delay
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            if delay < self.upgrade_delay {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowUpgradeLocked),
+                });
+                return Err(Error::EscrowUpgradeLocked);
+            }
+
+            self.upgrade_delay = delay;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the upgrade delay and the proposed code hash with the block from which
+        /// it can be set
+        #[ink(message, selector = 0xF8E6FE53)]
+        pub fn get_upgrade(&self) -> (BlockNumber, Option<(Hash, BlockNumber)>) {
+            (self.upgrade_delay, self.pending_upgrade)
+        }
+
+        /// Convert the stored state to the layout of this code after an upgrade, one
+        /// version step at a time. Nothing to do when the state is current.
+        #[ink(message, selector = 0x060D3F50)]
+        pub fn migrate(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+
+            let error = match self.version {
+                _ if caller != self.owner => Some(Error::BadOrigin),
+                version if version > STORAGE_VERSION => Some(Error::EscrowVersionUnsupported),
+                _ => None,
+            };
+            if let Some(error) = error {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(error.clone()),
+                });
+                return Err(error);
+            }
+
+            let from = self.version;
+            while self.version < STORAGE_VERSION {
+                self.migrate_step(self.version);
+                self.version += 1;
+            }
+
+            if from != self.version {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitSuccess(Success::EscrowMigrated { from, to: self.version }),
+                });
+            }
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Convert the state of layout `version` to `version + 1`. A layout change
+        /// bumps `STORAGE_VERSION` and adds its step here, e.g. moving the accounts to
+        /// a new container or filling a new field of every account.
+        fn migrate_step(&mut self, version: u32) {
+            match version {
+                // Version 0 is the layout before versioning, identical to version 1
+                0 => {}
+                _ => unreachable!("no migration step from version {}", version),
+            }
+        }
+
+        /// Get the storage layout version of the stored state and of this code
+        #[ink(message, selector = 0x3B47039B)]
+        pub fn storage_version(&self) -> (u32, u32) {
+            (self.version, STORAGE_VERSION)
+        }
+
+        /// Get the last block a dispute may be raised on an escrow account
+        #[ink(message, selector = 0x08B688BE)]
+        pub fn dispute_deadline(&self, reference: u16) -> Option<BlockNumber> {
+            if !self.feature_enabled(FEATURE_DISPUTES) {
+                return None;
+            }
+            self.account_by_reference(reference)
+                .map(|a| a.added_at.saturating_add(self.dispute_window))
+        }
+
+        /// Raise a dispute on an escrow account as its depositor or local recipient
+        /// within the dispute window, posting the dispute bond. The account is frozen
+        /// until the arbiter resolves the dispute.
+        #[ink(message, selector = 0x973C7100)]
+        pub fn raise_dispute(&mut self, reference: u16) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            let account = match self.account_by_reference(reference) {
+                _ if !self.feature_enabled(FEATURE_DISPUTES) => Err(Error::EscrowFeatureDisabled),
+                None => Err(Error::EscrowAccountNotFound),
+                Some(a) if a.account != caller && a.recipient != Recipient::Local(caller) => Err(Error::BadOrigin),
+                Some(a) if a.status == 2 => Err(Error::EscrowAccountDisputed),
+                Some(a) if a.status == 0 => Err(Error::EscrowAccountFrozen),
+                Some(a) if a.status == 3 => Err(Error::EscrowAccountPending),
+                Some(a) if self.env().block_number() > a.added_at.saturating_add(self.dispute_window) => {
+                    Err(Error::EscrowDisputeWindowClosed)
+                }
+                Some(a) => Ok(a),
+            };
+            let mut account = match account {
+                Ok(account) => account,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            // The arbiter registry draws the arbiter of the dispute
+            let arbiter = match self.arbiter_registry {
+                Some(registry) => {
+                    let seed: Hash = self.env()
+                        .hash_encoded::<Blake2x256, _>(&(b"arbiter", reference, caller, self.env().block_number()))
+                        .into();
+                    let mut registry: ink::contract_ref!(ArbiterRegistry) = registry.into();
+                    // A party to the escrow never rules its own dispute
+                    match registry.draw_arbiter(seed) {
+                        Some(arbiter) if arbiter != account.account
+                            && account.recipient != Recipient::Local(arbiter)
+                            && arbiter != self.manager => Some(arbiter),
+                        _ => {
+                            self.env().emit_event(EscrowEvent {
+                                operator: caller,
+                                status: EscrowStatus::EmitError(Error::EscrowArbiterUnavailable),
+                            });
+                            return Err(Error::EscrowArbiterUnavailable.into());
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            // The raiser posts the dispute bond
+            let bond = self.dispute_bond;
+            if bond > 0 {
+                if let Err(error) = self.pull(self.asset_id, caller, bond) {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(Error::EscrowTransferFailed),
+                    });
+                    return Err(error.into());
+                }
+                self.dispute_bonds = self.dispute_bonds.saturating_add(bond);
+            }
+
+            account.status = 2;
+            self.save_account(&account);
+            self.disputes.insert(reference, &Dispute {
+                raiser: caller,
+                bond,
+                raised_at: self.env().block_number(),
+                arbiter,
+            });
+            self.record_history(reference, Activity::Disputed);
+
+            let operation = Success::EscrowDisputeRaised { reference };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(account.correlation_id, reference, operation);
+            if let Some(arbiter) = arbiter {
+                let operation = Success::EscrowArbiterAssigned { reference, arbiter };
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitSuccess(operation.clone()),
+                });
+                self.emit_account_event(account.correlation_id, reference, operation);
+            }
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Resolve the dispute on an escrow account as an arbiter, releasing it to the
+        /// recipient, refunding it or splitting it. The raiser gets its bond back if
+        /// it wins or on a split, the counterparty receives it otherwise.
+        ///
+        /// With an arbiter panel, each member submits a ruling and the dispute is
+        /// settled on the majority outcome, or on the median of the recipient basis
+        /// points once the whole panel ruled without a majority. An arbiter drawn from
+        /// the arbiter registry rules its dispute alone.
+        #[ink(message, selector = 0x539B8B08)]
+        pub fn resolve_dispute(&mut self, reference: u16, outcome: DisputeOutcome) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+            let drawn = self.disputes.get(reference).and_then(|d| d.arbiter);
+            let panel = drawn.is_none() && !self.arbiter_panel.is_empty();
+
+            let resolution = match (self.disputes.get(reference), self.account_by_reference(reference)) {
+                _ if drawn.is_some_and(|arbiter| arbiter != caller) => Err(Error::BadOrigin),
+                _ if panel && !self.arbiter_panel.contains(&caller) => Err(Error::BadOrigin),
+                _ if drawn.is_none() && !panel && !self.has_role(Role::Arbiter, caller) => Err(Error::BadOrigin),
+                (Some(_), Some(_)) if matches!(outcome, DisputeOutcome::Split(bps) if bps > 10_000) => {
+                    Err(Error::EscrowSplitInvalid)
+                }
+                (Some(_), Some(_)) if panel && self.dispute_rulings.get(reference)
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|(arbiter, _)| *arbiter == caller) => Err(Error::EscrowAlreadyRuled),
+                (Some(dispute), Some(a)) => Ok((dispute, a)),
+                _ => Err(Error::EscrowDisputeNotFound),
+            };
+            let (dispute, a) = match resolution {
+                Ok(resolution) => resolution,
+                Err(error) => {
+                    self.env().emit_event(EscrowEvent {
+                        operator: caller,
+                        status: EscrowStatus::EmitError(error.clone()),
+                    });
+                    return Err(error.into());
+                }
+            };
+
+            // Rulings of former panel members are dropped, the dispute waits until
+            // the panel reaches an outcome
+            let mut rulings: Vec<Ruling> = if panel {
+                self.dispute_rulings.get(reference)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(arbiter, _)| self.arbiter_panel.contains(arbiter))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            rulings.push((caller, outcome));
+            let outcome = if panel {
+                match self.panel_outcome(&rulings) {
+                    Some(outcome) => outcome,
+                    None => {
+                        self.dispute_rulings.insert(reference, &rulings);
+
+                        let operation = Success::EscrowDisputeRuled { reference, rulings: rulings.len() as u8 };
+                        self.env().emit_event(EscrowEvent {
+                            operator: caller,
+                            status: EscrowStatus::EmitSuccess(operation.clone()),
+                        });
+                        self.emit_account_event(a.correlation_id, reference, operation);
+
+                        return Ok(());
+                    }
+                }
+            } else {
+                outcome
+            };
+
+            // The recipient part goes through the payout, the rest back to the
+            // contributors
+            let to_recipient = match outcome {
+                DisputeOutcome::Release => a.balance,
+                DisputeOutcome::Refund => 0,
+                DisputeOutcome::Split(bps) => a.balance.saturating_mul(bps as u128) / 10_000,
+            };
+            if to_recipient > 0 {
+                self.payout(&a, to_recipient)?;
+            }
+            let mut rest = a.clone();
+            rest.balance = a.balance - to_recipient;
+            self.refund_contributors(&rest)?;
+
+            // Settle the bond, a split has no loser
+            let raised_by_depositor = dispute.raiser == a.account;
+            let raiser_won = match outcome {
+                DisputeOutcome::Release => !raised_by_depositor,
+                DisputeOutcome::Refund => raised_by_depositor,
+                DisputeOutcome::Split(_) => true,
+            };
+            if dispute.bond > 0 {
+                self.dispute_bonds = self.dispute_bonds.saturating_sub(dispute.bond);
+                if raiser_won {
+                    self.transfer(self.asset_id, dispute.raiser, dispute.bond, None)?;
+                } else if raised_by_depositor {
+                    self.pay_recipient(&a.recipient, self.asset_id, dispute.bond, reference)?;
+                } else {
+                    self.transfer(self.asset_id, a.account, dispute.bond, None)?;
+                }
+            }
+
+            // Any ruling other than a release overturns the book of the manager
+            if outcome != DisputeOutcome::Release {
+                let mut metrics = self.manager_metrics.get(self.manager).unwrap_or_default();
+                metrics.disputes_lost += 1;
+                self.manager_metrics.insert(self.manager, &metrics);
+            }
+
+            // Arbiters ruling the executed outcome are rewarded, the overruled ones
+            // slashed
+            for (arbiter, ruling) in rulings.iter() {
+                let mut stats = self.arbiter_stats.get(arbiter).unwrap_or_default();
+                stats.rulings += 1;
+                if *ruling == outcome {
+                    stats.rewarded += 1;
+                } else {
+                    stats.slashed += 1;
+                }
+                self.arbiter_stats.insert(arbiter, &stats);
+            }
+
+            self.disputes.remove(reference);
+            self.dispute_rulings.remove(reference);
+            self.remove_account(&a, Activity::DisputeResolved);
+
+            let operation = Success::EscrowDisputeResolved { reference, outcome };
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(operation.clone()),
+            });
+            self.emit_account_event(a.correlation_id, reference, operation);
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the dispute pending on an escrow account
+        #[ink(message, selector = 0x4CFD8CC7)]
+        pub fn get_dispute(&self, reference: u16) -> Option<Dispute> {
+            self.disputes.get(reference)
+        }
+
+        /// Outcome reached by the rulings of the arbiter panel: the outcome of a
+        /// majority, otherwise the median recipient share once the whole panel ruled
+        fn panel_outcome(&self, rulings: &[(AccountId, DisputeOutcome)]) -> Option<DisputeOutcome> {
+            let size = self.arbiter_panel.len();
+            for (_, outcome) in rulings.iter() {
+                let votes = rulings.iter().filter(|(_, o)| o == outcome).count();
+                if votes * 2 > size {
+                    return Some(*outcome);
+                }
+            }
+            if rulings.len() < size {
+                return None;
+            }
+            let mut shares: Vec<u16> = rulings.iter().map(|(_, o)| o.recipient_bps()).collect();
+            shares.sort_unstable();
+            Some(match shares[shares.len() / 2] {
+                10_000 => DisputeOutcome::Release,
+                0 => DisputeOutcome::Refund,
+                bps => DisputeOutcome::Split(bps),
+            })
+        }
+
+        /// Set the arbiter panel ruling the disputes, 3 or 5 distinct accounts other
+        /// than the manager; an empty panel leaves the disputes to any holder of the
+        /// `Arbiter` role
+        #[ink(message, selector = 0x891DBDAD)]
+        pub fn set_arbiter_panel(&mut self, panel: Vec<AccountId>) -> Result<(), Error> {
+
+            // Only the owner can change the panel
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            let distinct = panel.iter()
+                .enumerate()
+                .all(|(i, arbiter)| !panel[..i].contains(arbiter));
+            if !matches!(panel.len(), 0 | 3 | 5) || !distinct || panel.contains(&self.manager) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowPanelInvalid),
+                });
+                return Err(Error::EscrowPanelInvalid);
+            }
+
+            self.arbiter_panel = panel;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the arbiter panel
+        #[ink(message, selector = 0xB9CFF62C)]
+        pub fn get_arbiter_panel(&self) -> Vec<AccountId> {
+            self.arbiter_panel.clone()
+        }
+
+        /// Get the panel rulings submitted on a pending dispute
+        #[ink(message, selector = 0x3EBC3BE3)]
+        pub fn get_rulings(&self, reference: u16) -> Vec<Ruling> {
+            self.dispute_rulings.get(reference).unwrap_or_default()
+        }
+
+        /// Get the ruling record of an arbiter
+        #[ink(message, selector = 0x2F729485)]
+        pub fn get_arbiter_stats(&self, arbiter: AccountId) -> ArbiterStats {
+            self.arbiter_stats.get(arbiter).unwrap_or_default()
+        }
+
+        /// Set the external arbiter registry the arbiter of each new dispute is drawn
+        /// from, `None` leaves the disputes to the arbiter panel or the `Arbiter` role
+        #[ink(message, selector = 0xE6C99B60)]
+        pub fn set_arbiter_registry(&mut self, registry: Option<AccountId>) -> Result<(), Error> {
+
+            // Only the owner decides who draws the arbiters
+            let caller = self.env().caller();
+            if caller != self.owner {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin);
+            }
+
+            self.arbiter_registry = registry;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Get the external arbiter registry
+        #[ink(message, selector = 0x24B9D900)]
+        pub fn get_arbiter_registry(&self) -> Option<AccountId> {
+            self.arbiter_registry
+        }
+
+        /// Get the activity history of an escrow account reference
+        #[ink(message, selector = 0x56E255F1)]
+        pub fn get_account_history(&self, reference: u16) -> Vec<History> {
+            self.history.get(reference).unwrap_or_default()
+        }
+
+        /// Record an activity in the bounded history of a reference
+        fn record_history(&mut self, reference: u16, activity: Activity) {
+            let mut history = self.history.get(reference).unwrap_or_default();
+            if history.len() >= MAXIMUM_HISTORY {
+                history.remove(0);
+            }
+            history.push(History {
+                activity,
+                block_number: self.env().block_number(),
+            });
+            self.history.insert(reference, &history);
+            self.count_operation();
+        }
+
+        /// Get the manager bond information (bond owner, bond, bond required)
+        #[ink(message, selector = 0xED31C3E1)]
+        pub fn get_bond(&self) -> (AccountId, u128, u128) {
+            (
+                self.bond_owner,
+                self.bond,
+                self.bond_required,
+            )
+        }
+
+        /// Post the manager bond, the manager must first approve the escrow contract
+        /// to spend the amount through `pallet_assets::approve_transfer`
+        #[ink(message, selector = 0x3AEEA3C1)]
+        pub fn post_bond(&mut self, amount: u128) -> Result<(), ContractError> {
+
+            // Posting the bond can only be done by the manager, a bond left by a
+            // previous manager must be withdrawn first.
+            let caller = self.env().caller();
+            if caller != self.manager || (self.bond > 0 && caller != self.bond_owner) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Pull the bond from the manager into the escrow
+            self.pull(self.asset_id, caller, amount)?;
+
+            self.bond = self.bond.saturating_add(amount);
+            self.bond_owner = caller;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowBondPosted { amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Withdraw the manager bond once the escrow is closed and emptied
+        #[ink(message, selector = 0x7B6209E0)]
+        pub fn withdraw_bond(&mut self) -> Result<(), ContractError> {
+
+            // Only the account that posted the bond can withdraw it
+            let caller = self.env().caller();
+            if caller != self.bond_owner || self.bond == 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            // Check if the escrow is close
+            if self.status == 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsOpen),
+                });
+                return Err(Error::EscrowIsOpen.into());
+            }
+
+            // Check if the escrow is empty
+            if self.account_count != 0 {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowIsNotEmpty),
+                });
+                return Err(Error::EscrowIsNotEmpty.into());
+            }
+
+            let amount = self.bond;
+            self.transfer(self.asset_id, caller, amount, None)?;
+
+
+            self.bond = 0;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowBondWithdrawn { amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Decommission the escrow: refund every remaining account to its contributors,
+        /// return the dispute bonds and the manager bond, send the dust pot to the
+        /// beneficiary and terminate the contract, whose storage deposit and native
+        /// balance also go to the beneficiary
+        #[ink(message, selector = 0x476D839F)]
+        pub fn terminate(&mut self, beneficiary: AccountId) -> Result<(), ContractError> {
+            let caller = self.env().caller();
+
+            // NFT escrows are released by their depositors, queued transfers settled
+            let error = if caller != self.owner {
+                Some(Error::BadOrigin)
+            } else if self.nft_count > 0 {
+                Some(Error::EscrowIsNotEmpty)
+            } else if self.queue_head != self.queue_tail {
+                Some(Error::EscrowQueueNotEmpty)
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(error.clone()),
+                });
+                return Err(error.into());
+            }
+
+            // Nothing may be left in the queue of a terminated contract
+            self.deferred_transfers = false;
+
+            for a in self.account_list() {
+                if let Some(dispute) = self.disputes.take(a.reference) {
+                    if dispute.bond > 0 {
+                        self.dispute_bonds = self.dispute_bonds.saturating_sub(dispute.bond);
+                        self.transfer(self.asset_id, dispute.raiser, dispute.bond, None)?;
+                    }
+                }
+
+                self.refund_contributors(&a)?;
+                self.remove_account(&a, Activity::Refunded);
+
+                let operation = Success::EscrowAccountRefunded { reference: a.reference, amount: a.balance };
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitSuccess(operation.clone()),
+                });
+                self.emit_account_event(a.correlation_id, a.reference, operation);
+            }
+
+            if self.bond > 0 {
+                let bond = self.bond;
+                self.bond = 0;
+                self.transfer(self.asset_id, self.bond_owner, bond, None)?;
+            }
+
+            // The escrow asset left, dust pot included, goes to the beneficiary, a
+            // `pallet_assets` account is swept whole so no surplus is stranded
+            let dust = core::mem::take(&mut self.dust_pot);
+            match self.backend {
+                TransferBackend::Assets => self
+                    .dispatch(&RuntimeCall::Assets(AssetsCall::TransferAll {
+                        id: self.asset_id,
+                        dest: beneficiary.into(),
+                        keep_alive: false,
+                    }))
+                    .map_err(|_| RuntimeError::CallRuntimeFailed)?,
+                _ if dust > 0 => self.transfer(self.asset_id, beneficiary, dust, None)?,
+                _ => {}
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowTerminated { beneficiary }),
+            });
+
+            self.env().terminate_contract(beneficiary)
+        }
+
+        /// Slash the manager bond on proven misbehavior as the owner or an arbiter,
+        /// the slashed amount is sent to the beneficiary
+        #[ink(message, selector = 0xF8B2707A)]
+        pub fn slash_bond(&mut self,
+            amount: u128,
+            beneficiary: AccountId) -> Result<(), ContractError> {
+
+            // Slashing can only be done by the owner or an arbiter
+            let caller = self.env().caller();
+            if caller != self.owner && !self.has_role(Role::Arbiter, caller) {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::BadOrigin),
+                });
+                return Err(Error::BadOrigin.into());
+            }
+
+            if amount > self.bond {
+                self.env().emit_event(EscrowEvent {
+                    operator: caller,
+                    status: EscrowStatus::EmitError(Error::EscrowBondInsufficient),
+                });
+                return Err(Error::EscrowBondInsufficient.into());
+            }
+
+            self.transfer(self.asset_id, beneficiary, amount, None)?;
+
+            self.bond -= amount;
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowBondSlashed { amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+    }
+
+    impl EscrowReceiver for Escrow {
+
+        /// Errors are returned instead of emitted so that the sending escrow reverts
+        #[ink(message)]
+        fn receive_position(&mut self,
+            asset_id: u128,
+            reference: u16,
+            account: AccountId,
+            amount: u128,
+            recipient: AccountId) -> Result<(), ContractError> {
+
+            // Only accepted escrows can transfer in, the account keeps its asset
+            let caller = self.env().caller();
+            if !self.accepted_escrows.contains(caller) {
+                return Err(Error::BadOrigin.into());
+            }
+
+            if self.adds_blocked() {
+                return Err(Error::EscrowIsClose.into());
+            }
+            if self.is_paused(PAUSE_ADD) {
+                return Err(Error::EscrowOperationPaused.into());
+            }
+            if !self.depositor_allowed(account) {
+                return Err(Error::EscrowDepositorNotAllowed.into());
+            }
+
+            if self.accounts.contains(account)
+                || self.references.contains(reference)
+                || self.nft_escrows.contains(reference) {
+                return Err(Error::EscrowAccountDuplicate.into());
+            }
+
+            if self.at_capacity() {
+                return Err(Error::EscrowAccountMax.into());
+            }
+
+            // Pull the funds approved by the sending escrow
+            self.pull(asset_id, caller, amount)?;
+
+            self.insert_account(&Account {
+                reference,
+                account,
+                asset_id,
+                balance: amount,
+                recipient: Recipient::Local(recipient),
+                status: 1, // 1 = Liquid
+                confirmed: !self.recipient_confirmation,
+                tags: Vec::new(),
+                template: None,
+                nft: self.nft_positions,
+                added_at: self.env().block_number(),
+                tx_hash: Hash::default(),
+                correlation_id: None,
+                paused_until: 0,
+                release_after: 0,
+                expires_at: 0,
+            });
+            self.record_history(reference, Activity::TransferredIn);
+
+            if self.nft_positions {
+                self.env().emit_event(Transfer {
+                    from: None,
+                    to: Some(account),
+                    id: Id::U16(reference),
+                });
+            }
+
+            self.env().emit_event(EscrowEvent {
+                operator: caller,
+                status: EscrowStatus::EmitSuccess(Success::EscrowAccountReceived { reference, amount }),
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+    }
+
+    impl PSP34 for Escrow {
+
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            let contract = self.env().account_id();
+            Id::Bytes(<AccountId as AsRef<[u8]>>::as_ref(&contract).to_vec())
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            // One escrow per account, so one position at most
+            self.accounts.get(owner).map_or(0, |a| a.nft as u32)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            self.position_of(&id).map(|a| a.account)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
+            self.nft_approvals.contains((owner, operator, None::<Id>))
+                || (id.is_some() && self.nft_approvals.contains((owner, operator, id)))
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<(), PSP34Error> {
+            let caller = self.env().caller();
+            if caller == operator {
+                return Err(PSP34Error::SelfApprove);
+            }
+
+            // Only the owner of the position can approve it
+            if let Some(id) = &id {
+                match self.position_of(id) {
+                    Some(a) if a.account == caller => {}
+                    Some(_) => return Err(PSP34Error::NotApproved),
+                    None => return Err(PSP34Error::TokenNotExists),
+                }
+            }
+
+            if approved {
+                self.nft_approvals.insert((caller, operator, id.clone()), &());
+            } else {
+                self.nft_approvals.remove((caller, operator, id.clone()));
+            }
+
+            self.env().emit_event(Approval {
+                owner: caller,
+                operator,
+                id,
+                approved,
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        /// Transfers the position NFT together with the depositor rights of the escrow
+        /// account
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, id: Id, _data: Vec<u8>) -> Result<(), PSP34Error> {
+            let caller = self.env().caller();
+
+            let mut a = self.position_of(&id).ok_or(PSP34Error::TokenNotExists)?;
+            let from = a.account;
+
+            if caller != from && !self.allowance(from, caller, Some(id.clone())) {
+                return Err(PSP34Error::NotApproved);
+            }
+
+            // One escrow per account only
+            if self.accounts.contains(to) {
+                return Err(PSP34Error::Custom(String::from("EscrowAccountDuplicate")));
+            }
+
+            // Positions only move to depositors the allow-list accepts
+            if !self.depositor_allowed(to) {
+                return Err(PSP34Error::Custom(String::from("EscrowDepositorNotAllowed")));
+            }
+
+            self.nft_approvals.remove((from, caller, Some(id.clone())));
+            self.move_account(&mut a, to);
+            let reference = a.reference;
+            self.record_history(reference, Activity::Transferred);
+
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                id,
+            });
+
+            self.assert_invariants();
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn total_supply(&self) -> u128 {
+            self.account_list().iter().filter(|a| a.nft).count() as u128
+        }
+    }
+
+    impl PSP34Metadata for Escrow {
+
+        /// Position attributes: `reference`, `amount` and `recipient`
+        #[ink(message)]
+        fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>> {
+            let a = self.position_of(&id)?;
+            match key.as_slice() {
+                b"reference" => Some(scale::Encode::encode(&a.reference)),
+                b"amount" => Some(scale::Encode::encode(&a.balance)),
+                b"recipient" => Some(scale::Encode::encode(&a.recipient)),
+                _ => None,
+            }
+        }
     }
 
-    /// Unit tests
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use crate::assets::CallIndices;
+
+        thread_local! {
+            /// Gas left seen by the batches, unlimited unless a test lowers it
+            pub static GAS_LEFT: core::cell::Cell<u64> = const { core::cell::Cell::new(u64::MAX) };
+        }
+
+        /// Status carried by the last escrow event emitted.
+        fn last_status() -> EscrowStatus {
+            let signature = <EscrowEvent as ink::env::Event>::SIGNATURE_TOPIC.unwrap();
+            let event = ink::env::test::recorded_events()
+                .filter(|e| e.topics.first().map(|t| t.as_slice()) == Some(&signature[..]))
+                .last()
+                .expect("no escrow event emitted");
+            <EscrowEvent as scale::Decode>::decode(&mut &event.data[..])
+                .expect("escrow event decodes")
+                .status
+        }
+
+        /// Number of `ReleaseDueSoon` events emitted.
+        fn due_soon_events() -> usize {
+            let signature = <ReleaseDueSoon as ink::env::Event>::SIGNATURE_TOPIC.unwrap();
+            ink::env::test::recorded_events()
+                .filter(|e| e.topics.first().map(|t| t.as_slice()) == Some(&signature[..]))
+                .count()
+        }
+
+        /// Decode the last emitted event of a type.
+        fn last_event<E: ink::env::Event + scale::Decode>() -> Option<E> {
+            let signature = <E as ink::env::Event>::SIGNATURE_TOPIC.unwrap();
+            ink::env::test::recorded_events()
+                .filter(|e| e.topics.first().map(|t| t.as_slice()) == Some(&signature[..]))
+                .last()
+                .map(|e| <E as scale::Decode>::decode(&mut &e.data[..]).expect("event decodes"))
+        }
+
+        /// Assert the call failed with the error and the last escrow event carries it.
+        fn assert_error<T: core::fmt::Debug, E: Into<ContractError>>(result: Result<T, E>, error: Error) {
+            let result: Result<T, ContractError> = result.map_err(Into::into);
+            assert_eq!(result.err(), Some(ContractError::Internal(error.clone())));
+            assert_eq!(last_status(), EscrowStatus::EmitError(error));
+        }
+
+        /// Assert a legacy message rejected the call through the last escrow event only.
+        fn assert_rejected<T: core::fmt::Debug, E: core::fmt::Debug>(result: Result<T, E>, error: Error) {
+            assert!(result.is_ok());
+            assert_eq!(last_status(), EscrowStatus::EmitError(error));
+        }
+
+        /// We test if the default constructor does its job.
+        #[ink::test]
+        fn default_works() {
+            let escrow = Escrow::default();
+            assert_eq!(escrow.account_count, 0);
+        }
+
+        /// We test if the operations summary counts accounts by status.
+        #[ink::test]
+        fn ops_summary_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.freeze(accounts.django).unwrap();
+
+            let summary = escrow.ops_summary();
+            assert_eq!(summary.status, 0);
+            assert_eq!(summary.accounts, 2);
+            assert_eq!(summary.frozen_accounts, 1);
+            assert_eq!(summary.liquid_accounts, 1);
+            assert_eq!(summary.total_liability, 150);
+        }
+
+        /// We test if the statistics count the released amount.
+        #[ink::test]
+        fn stats_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(3u16, accounts.eve, 1u128, 25u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.freeze(accounts.django).unwrap();
+            escrow.force_release_partial(accounts.eve, accounts.charlie, 5u128, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+
+            let stats = escrow.stats();
+            assert_eq!(stats.total_balance, 70);
+            assert_eq!(stats.accounts, 2);
+            assert_eq!(stats.frozen_accounts, 1);
+            assert_eq!(stats.liquid_accounts, 1);
+            assert_eq!(stats.disputed_accounts, 0);
+            assert_eq!(stats.total_released, 105);
+        }
+
+        /// We test if the account history is recorded per reference.
+        #[ink::test]
+        fn account_history_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.add(7u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            let history = escrow.get_account_history(7u16);
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].activity, Activity::Added);
+            assert!(escrow.get_account_history(8u16).is_empty());
+        }
+
+        /// We test if add_auto assigns monotonic references skipping used ones.
+        #[ink::test]
+        fn add_auto_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.add(0u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.next_reference(), 1u16);
+
+            escrow.add_auto(accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.accounts.get(accounts.django).unwrap().reference, 1u16);
+            assert_eq!(escrow.next_reference(), 2u16);
+        }
+
+        /// We test if the error codes stay stable.
+        #[ink::test]
+        fn error_codes_are_stable() {
+            assert_eq!(Error::BadOrigin.error_code(), 0);
+            assert_eq!(Error::EscrowAccountMax.error_code(), 4);
+            assert_eq!(Error::EscrowTemplateInvalid.error_code(), 13);
+            assert_eq!(ContractError::from(Error::EscrowIsClose).error_code(), 1);
+            assert_eq!(ContractError::from(RuntimeError::CallRuntimeFailed).error_code(), 256);
+            assert_eq!(RuntimeError::UnknownAsset.error_code(), 263);
+            assert_eq!(scale::Encode::encode(&Error::EscrowAccountMax), [4u8]);
+        }
+
+        /// We test if the transfer check codes map to the runtime errors.
+        #[ink::test]
+        fn transfer_check_codes_work() {
+            assert_eq!(RuntimeError::from_transfer_check(1), RuntimeError::BalanceLow);
+            assert_eq!(RuntimeError::from_transfer_check(2), RuntimeError::NoAccount);
+            assert_eq!(RuntimeError::from_transfer_check(3), RuntimeError::AssetFrozen);
+            assert_eq!(RuntimeError::from_transfer_check(4), RuntimeError::BelowMinimum);
+            assert_eq!(RuntimeError::from_transfer_check(5), RuntimeError::UnknownAsset);
+            assert_eq!(RuntimeError::from_transfer_check(0), RuntimeError::CallRuntimeFailed);
+            assert_eq!(RuntimeError::from_transfer_check(9), RuntimeError::CallRuntimeFailed);
+        }
+
+        /// We test if a position NFT transfer moves the depositor rights.
+        #[ink::test]
+        fn nft_position_transfer_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.set_nft_positions(true).unwrap();
+            escrow.add(3u16, accounts.alice, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(PSP34::owner_of(&escrow, Id::U16(3u16)), Some(accounts.alice));
+            assert_eq!(PSP34::total_supply(&escrow), 1);
+
+            PSP34::transfer(&mut escrow, accounts.bob, Id::U16(3u16), Vec::new()).unwrap();
+            assert_eq!(PSP34::owner_of(&escrow, Id::U16(3u16)), Some(accounts.bob));
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().reference, 3u16);
+            assert!(escrow.accounts.get(accounts.alice).is_none());
+            assert_eq!(
+                PSP34::transfer(&mut escrow, accounts.django, Id::U16(3u16), Vec::new()),
+                Err(PSP34Error::NotApproved)
+            );
+        }
+
+        /// We test if an expired intent cannot be confirmed and is purged.
+        #[ink::test]
+        fn intent_expiry_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.set_require_intents(true, 1).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.register_intent(1u16, 100u128, accounts.charlie).unwrap();
+            assert_eq!(escrow.intent_queue.get(0), Some((accounts.bob, 0)));
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_rejected(escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None), Error::EscrowIntentExpired);
+
+            escrow.purge_expired_intents(10).unwrap();
+            assert_eq!(escrow.get_intent(accounts.bob), None);
+            assert_eq!((escrow.intent_head, escrow.intent_tail), (1, 1));
+        }
+
+        /// We test if the purge drops stale queue entries, stops at the first live
+        /// intent and the queue is capped.
+        #[ink::test]
+        fn intent_queue_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_require_intents(true, 1).unwrap();
+
+            // Bob replaces his intent and Django cancels his, leaving stale entries
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.register_intent(1u16, 100u128, accounts.charlie).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.register_intent(2u16, 50u128, accounts.charlie).unwrap();
+            escrow.cancel_intent().unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.register_intent(1u16, 120u128, accounts.charlie).unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.register_intent(3u16, 70u128, accounts.charlie).unwrap();
+            assert_eq!(escrow.intent_tail, 4);
+
+            // Bob's live intent stops the purge, the stale entries before it go
+            escrow.purge_expired_intents(10).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowIntentsPurged { purged: 0 }));
+            assert_eq!(escrow.intent_head, 2);
+            assert_eq!(escrow.get_intent(accounts.bob).unwrap().amount, 120);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            escrow.purge_expired_intents(1).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowIntentsPurged { purged: 1 }));
+            assert_eq!(escrow.get_intent(accounts.bob), None);
+            assert_eq!(escrow.get_intent(accounts.eve).unwrap().amount, 70);
+
+            escrow.intent_tail = escrow.intent_head.wrapping_add(MAXIMUM_INTENTS);
+            assert_error(escrow.register_intent(3u16, 70u128, accounts.charlie), Error::EscrowIntentMax);
+        }
+
+        /// We test if third-party funding rejects zero amounts and unknown references.
+        #[ink::test]
+        fn fund_checks_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.fund(1u16, 0u128), Error::EscrowAmountInvalid);
+            assert_error(escrow.fund(2u16, 10u128), Error::EscrowAccountNotFound);
+            assert!(escrow.get_contributions(1u16).iter().all(|c| c.contributor != accounts.django));
+        }
+
+        /// We test if frozen accounts are not releasable, singly and in batches.
+        #[ink::test]
+        fn freeze_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.eve, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_error(escrow.freeze(accounts.django), Error::EscrowAccountNotFound);
+            escrow.freeze(accounts.bob).unwrap();
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().status, 0);
+
+            let outcome = escrow.freeze_batch(ink::prelude::vec![accounts.django, accounts.eve]).unwrap();
+            assert_eq!(
+                outcome.results,
+                ink::prelude::vec![
+                    BatchResult { account: accounts.django, error: Some(Error::EscrowAccountNotFound) },
+                    BatchResult { account: accounts.eve, error: None },
+                ]
+            );
+            assert_eq!(outcome.cursor, None);
+            assert_eq!(escrow.accounts.get(accounts.eve).unwrap().status, 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowAccountFrozen);
+            assert_error(escrow.freeze_batch(ink::prelude::vec![accounts.bob]), Error::BadOrigin);
+        }
+
+        /// We test if batches stop before the gas reserve and return the cursor of
+        /// the first unprocessed entry.
+        #[ink::test]
+        fn batch_gas_reserve_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            GAS_LEFT.with(|gas| gas.set(BATCH_GAS_RESERVE - 1));
+            let outcome = escrow.freeze_batch(ink::prelude::vec![accounts.bob]).unwrap();
+            assert_eq!(outcome, BatchOutcome { results: Vec::new(), cursor: Some(0) });
+            let event = last_event::<BatchPartiallyProcessed>().unwrap();
+            assert_eq!((event.operator, event.cursor, event.total), (accounts.alice, 0, 1));
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().status, 1);
+
+            let outcome = escrow.force_release_batch(ink::prelude::vec![accounts.bob]).unwrap();
+            assert_eq!(outcome.cursor, Some(0));
+            assert_eq!(escrow.account_count, 1);
+
+            // The entries from the cursor on are sent again once gas is available
+            GAS_LEFT.with(|gas| gas.set(BATCH_GAS_RESERVE));
+            let outcome = escrow.force_release_batch(ink::prelude::vec![accounts.bob]).unwrap();
+            assert_eq!(outcome.cursor, None);
+            assert_eq!(escrow.account_count, 0);
+        }
+
+        /// We test if only account transfers below the dust threshold go to the dust pot
+        /// and if the owner can withdraw it.
+        #[ink::test]
+        fn dust_policy_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.set_dust_policy(10u128, 1).unwrap();
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 9u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 10u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            assert_eq!(escrow.dust_pot, 9);
+            assert_eq!(escrow.queued_amount, 0);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.release().unwrap();
+            assert_eq!(escrow.dust_pot, 9);
+            assert_eq!(escrow.queued_amount, 10);
+            assert_eq!(escrow.account_count, 0);
+
+            // Only the owner withdraws the dust pot
+            assert_error(escrow.withdraw_dust(accounts.eve), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.withdraw_dust(accounts.eve).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowDustWithdrawn { amount: 9 }));
+            assert_eq!(escrow.dust_pot, 0);
+            let pending = escrow.transfer_queue.get(1).unwrap();
+            assert_eq!((pending.target, pending.amount, pending.reference), (accounts.eve, 9, None));
+            assert_eq!(escrow.queued_amount, 19);
+        }
+
+        /// We test if references stay unique and removals keep the account index
+        /// consistent.
+        #[ink::test]
+        fn account_index_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(escrow.add(1u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None), Error::EscrowAccountDuplicate);
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(3u16, accounts.eve, 1u128, 25u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.ops_summary().total_liability, 175);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            assert_eq!(escrow.account_count, 2);
+            assert_eq!(escrow.account_slots.get(0), Some(accounts.eve));
+            assert_eq!(escrow.slot_of.get(accounts.eve), Some(0));
+            assert_eq!(escrow.references.get(1u16), None);
+            assert_eq!(escrow.ops_summary().total_liability, 75);
+        }
+
+        /// We test if checkpoints follow the operation interval and the accounts root
+        /// tracks the accounts.
+        #[ink::test]
+        fn checkpoint_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            assert_eq!(escrow.accounts_root(), Hash::default());
+
+            escrow.set_checkpoint_interval(2).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            let root = escrow.accounts_root();
+            assert_ne!(root, Hash::default());
+            assert_eq!(escrow.checkpoint_counter, 0);
+
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.checkpoint_counter, 1);
+            assert_eq!(escrow.operations_since_checkpoint, 0);
+            assert_ne!(escrow.accounts_root(), root);
+
+            escrow.checkpoint().unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowCheckpointEmitted { checkpoint: 2 }));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.checkpoint(), Error::BadOrigin);
+        }
+
+        /// We test if disabled feature groups are refused.
+        #[ink::test]
+        fn feature_flags_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new_with_features(1u128, 10u16, FEATURE_ALL & !FEATURE_FORCE_RELEASE & !FEATURE_DISPUTES);
+            assert_eq!(escrow.features(), FEATURE_FEES | FEATURE_VESTING | FEATURE_CLAIMS);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(escrow.force_release(accounts.bob, accounts.charlie, None), Error::EscrowFeatureDisabled);
+            assert_error(escrow.set_dispute_bond(10), Error::EscrowFeatureDisabled);
+            assert_eq!(escrow.dispute_deadline(1u16), None);
+            assert_eq!(escrow.account_count, 1);
+        }
+
+        /// We test if a deposit cannot take a used reference. Successful deposits pull
+        /// the funds through `call_runtime`, which the off-chain environment does not
+        /// support.
+        #[ink::test]
+        fn deposit_duplicate_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.deposit(1u16, 50u128, accounts.charlie), Error::EscrowAccountDuplicate);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.deposit(2u16, 50u128, accounts.charlie), Error::EscrowAccountDuplicate);
+        }
+
+        /// We test if a heartbeat without a registry fails.
+        #[ink::test]
+        fn heartbeat_without_registry_fails() {
+            let mut escrow = Escrow::new(1u128, 10u16);
+            assert_error(escrow.heartbeat(), Error::EscrowRegistryNotSet);
+        }
+
+        /// We test if the fixture builder adds deterministic accounts.
+        #[cfg(feature = "test-fixtures")]
+        #[ink::test]
+        fn fixture_works() {
+            use crate::fixtures::EscrowFixture;
+
+            let fixture = EscrowFixture::with_accounts(3).base_amount(10);
+            let escrow = fixture.build();
+            assert_eq!(escrow.account_count, 3);
+            assert_eq!(escrow.total_balance, 60);
+            assert_eq!(escrow.account_by_reference(2).unwrap().account, EscrowFixture::depositor(2));
+            assert_eq!(fixture, EscrowFixture::with_accounts(3).base_amount(10));
+        }
+
+        /// We test if partial releases keep the remainder and remove emptied accounts.
+        #[ink::test]
+        fn release_partial_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.force_release_partial(accounts.bob, accounts.charlie, 30u128, None).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountPartiallyReleased { reference: 1, amount: 30, fee: 0, remaining: 70 })
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.release_partial(71u128), Error::EscrowAmountInvalid);
+            escrow.release_partial(70u128).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 70, fee: 0 }));
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.queued_amount, 100);
+        }
+
+        /// We test if timelocks are announced when set and once more near their end.
+        #[ink::test]
+        fn release_due_soon_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.pause_my_account().unwrap();
+            assert_eq!(due_soon_events(), 1);
+
+            // A notice period covering the whole pause announces it on the next operation
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_release_notice(ACCOUNT_PAUSE_DURATION).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(due_soon_events(), 2);
+            assert_eq!(escrow.notified_due.get(1), Some(ACCOUNT_PAUSE_DURATION));
+
+            escrow.add(3u16, accounts.eve, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(due_soon_events(), 2);
+        }
+
+        /// We test if the owner and manager are rotated in two steps, keeping the accounts.
+        #[ink::test]
+        fn two_step_rotation_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            escrow.set_manager(accounts.django).unwrap();
+            assert_eq!(escrow.manager, accounts.alice);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_error(escrow.accept_manager(), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.accept_manager().unwrap();
+            assert_eq!(escrow.manager, accounts.django);
+            assert_eq!(escrow.pending_manager, None);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.transfer_ownership(accounts.eve).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.accept_ownership().unwrap();
+            assert_eq!(escrow.owner, accounts.eve);
+            assert_eq!(escrow.account_count, 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_error(escrow.transfer_ownership(accounts.alice), Error::BadOrigin);
+        }
+
+        /// We test if a close is refused while accounts hold a balance when required.
+        #[ink::test]
+        fn close_requires_empty_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.setup(1u128, accounts.alice, 10u16, 0u128, 0u8, 10, 10, true).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            assert_rejected(escrow.close(), Error::EscrowIsNotEmpty);
+            assert_eq!(escrow.status, 0);
+        }
+
+        /// We test if the manager and the recipient can refund an account to its depositor.
+        #[ink::test]
+        fn refund_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            escrow.refund(accounts.bob).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountRefunded { reference: 1, amount: 100 }));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_error(escrow.refund(accounts.django), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.refund(accounts.django).unwrap();
+
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.queued_amount, 150);
+            assert_eq!(escrow.transfer_queue.get(0).map(|t| t.target), Some(accounts.bob));
+        }
+
+        /// We test if a manager override waits for the owner countersignature.
+        #[ink::test]
+        fn dual_control_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.set_dual_control(10).unwrap();
+            escrow.set_manager(accounts.django).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.accept_manager().unwrap();
+
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowReleaseProposed { id: 0 }));
+            assert_eq!(escrow.account_count, 1);
+            assert_error(escrow.approve_release(0), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.approve_release(0).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 100, fee: 0 }));
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.get_manager_metrics(accounts.django).unwrap().releases_approved, 1);
+            assert_error(escrow.approve_release(0), Error::EscrowPendingReleaseNotFound);
+        }
+
+        /// We test if only an account with a failed release is converted into a refund,
+        /// through the override approvals.
+        #[ink::test]
+        fn convert_to_refund_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_error(escrow.convert_to_refund(accounts.bob), Error::EscrowReleaseNotFailed);
+
+            // The failure `force_release_batch` records when the transfer fails
+            escrow.release_failures.insert(accounts.bob, &1);
+            let mut disputed = escrow.accounts.get(accounts.bob).unwrap();
+            disputed.status = 2;
+            escrow.accounts.insert(accounts.bob, &disputed);
+            assert_error(escrow.convert_to_refund(accounts.bob), Error::EscrowAccountDisputed);
+            disputed.status = 1;
+            escrow.accounts.insert(accounts.bob, &disputed);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.convert_to_refund(accounts.bob), Error::BadOrigin);
+
+            // Under the dual control the owner countersigns the refund
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_dual_control(10).unwrap();
+            escrow.set_manager(accounts.django).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.accept_manager().unwrap();
+            escrow.convert_to_refund(accounts.bob).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowReleaseProposed { id: 0 }));
+            assert_eq!(escrow.account_count, 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.approve_release(0).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountConverted { reference: 1 }));
+            assert_eq!(escrow.account_count, 0);
+            assert!(!escrow.release_failures.contains(accounts.bob));
+            let refund = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((refund.target, refund.amount), (accounts.bob, 100));
+            assert_eq!(escrow.get_account_history(1).last().unwrap().activity, Activity::ConvertedToRefund);
+        }
+
+        /// We test if an override release needs the approvals of a quorum of managers.
+        #[ink::test]
+        fn quorum_release_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_error(escrow.set_quorum(ink::prelude::vec![accounts.alice], 2, 10), Error::EscrowQuorumInvalid);
+            escrow.set_quorum(ink::prelude::vec![accounts.alice, accounts.django, accounts.eve], 3, 10).unwrap();
+
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowReleaseProposed { id: 0 }));
+            assert_error(escrow.approve_release(0), Error::EscrowAlreadyApproved);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_error(escrow.approve_release(0), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.approve_release(0).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowReleaseApproved { id: 0, approvals: 2 }));
+            assert_eq!(escrow.account_count, 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.approve_release(0).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 100, fee: 0 }));
+            assert_eq!(escrow.account_count, 0);
+            assert!(escrow.get_release_approvals(0).is_empty());
+        }
+
+        /// We test if a locked account cannot be released before its lock period passed.
+        #[ink::test]
+        fn release_lock_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, Some(2), None).unwrap();
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().release_after, 2);
+            assert_eq!(due_soon_events(), 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowStillLocked);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            escrow.release().unwrap();
+            assert_eq!(escrow.account_count, 0);
+        }
+
+        /// We test if anyone can send an expired account back to its depositor.
+        #[ink::test]
+        fn reclaim_expired_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_error(escrow.reclaim_expired(accounts.bob), Error::EscrowNotExpired);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_expiry(1, 1).unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.reclaim_expired(accounts.bob).unwrap();
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.transfer_queue.get(0).map(|t| t.target), Some(accounts.bob));
+        }
+
+        /// We test if the outflow counters sum the payouts of the window.
+        #[ink::test]
+        fn outflow_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.get_outflow(OUTFLOW_BUCKET_BLOCKS), 0);
+
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            escrow.refund(accounts.django).unwrap();
+            assert_eq!(escrow.get_outflow(1), 150);
+            assert_eq!(escrow.get_outflow(0), 0);
+        }
+
+        /// We test if accounts in another asset are paid and accounted in their asset.
+        #[ink::test]
+        fn multi_asset_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 2u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.total_balance, 100);
+            assert_eq!(escrow.liability_of(2), 50);
+            assert_error(escrow.rescue_asset(2, 10, accounts.eve), Error::EscrowAssetProtected);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.release().unwrap();
+            let pending = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((pending.asset_id, pending.amount), (2, 50));
+            assert_eq!(escrow.queued_amount, 0);
+            assert_eq!(escrow.liability_of(2), 50);
+            assert_eq!(escrow.asset_balances.get(2), None);
+        }
+
+        /// We test if a settlement run sums the transfers per asset and target.
+        #[ink::test]
+        fn aggregate_transfers_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let pending = |asset_id: u128, target: AccountId, amount: u128| PendingTransfer {
+                asset_id,
+                target,
+                amount,
+                reference: None,
+                attempts: 0,
+            };
+
+            let totals = Escrow::aggregate_transfers(&[
+                pending(1, accounts.charlie, 100),
+                pending(1, accounts.bob, 10),
+                pending(1, accounts.charlie, 50),
+                pending(2, accounts.charlie, 5),
+            ]);
+            assert_eq!(totals, vec![
+                (1, accounts.charlie, 150),
+                (1, accounts.bob, 10),
+                (2, accounts.charlie, 5),
+            ]);
+        }
+
+        /// We test if the default lock period applies when an add omits it.
+        #[ink::test]
+        fn default_terms_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_default_terms(5, 10, 20).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowTermsUpdated { lock_period: 5, dispute_window: 10, cancel_window: 20 })
+            );
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, Some(0), None).unwrap();
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().release_after, 5);
+            assert_eq!(escrow.accounts.get(accounts.django).unwrap().release_after, 0);
+            assert_eq!(escrow.dispute_deadline(1), Some(10));
+        }
+
+        /// We test if the PSP22 backend only holds the escrow asset.
+        #[ink::test]
+        fn psp22_backend_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let escrow = Escrow::new_with_psp22(1u128, 10u16, accounts.frank);
+            assert_eq!(escrow.get_transfer_backend(), TransferBackend::Psp22(accounts.frank));
+            assert_eq!(escrow.backend_of(1), TransferBackend::Psp22(accounts.frank));
+            assert_eq!(escrow.backend_of(2), TransferBackend::Assets);
+            assert_eq!(Escrow::new(1u128, 10u16).backend_of(1), TransferBackend::Assets);
+        }
+
+        /// We test if an approved spender releases parts of an account up to its cap.
+        #[ink::test]
+        fn release_from_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.approve_spender(accounts.frank, 50u128).unwrap();
+            assert_eq!(escrow.get_allowance(accounts.bob, accounts.frank), 50);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            escrow.release_from(accounts.bob, 30u128).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountPartiallyReleased { reference: 1, amount: 30, fee: 0, remaining: 70 })
+            );
+            assert_eq!(escrow.get_allowance(accounts.bob, accounts.frank), 20);
+            assert_error(escrow.release_from(accounts.bob, 21u128), Error::EscrowAllowanceExceeded);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.revoke_spender(accounts.frank).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_error(escrow.release_from(accounts.bob, 10u128), Error::EscrowAllowanceExceeded);
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().balance, 70);
+        }
+
+        /// We test if a native escrow opens accounts with the transferred value.
+        #[ink::test]
+        fn deposit_native_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new_native(0u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.deposit_native(1u16, accounts.charlie), Error::EscrowAmountInvalid);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            escrow.deposit_native(1u16, accounts.charlie).unwrap();
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().balance, 100);
+            assert_eq!(escrow.total_balance, 100);
+
+            escrow.release_partial(40u128).unwrap();
+            assert_eq!(escrow.total_balance, 60);
+
+            let mut assets = Escrow::new(0u128, 10u16);
+            assert_error(assets.deposit_native(1u16, accounts.charlie), Error::EscrowWrongBackend);
+        }
+
+        /// We test if a repeat deposit to the own reference tops up the account.
+        #[ink::test]
+        fn deposit_top_up_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new_native(0u128, 10u16);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            escrow.deposit_native(1u16, accounts.charlie).unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(30);
+            escrow.deposit_native(1u16, accounts.charlie).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountToppedUp { reference: 1, amount: 30 }));
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().balance, 130);
+            assert_eq!(escrow.total_balance, 130);
+            assert_eq!(escrow.account_count, 1);
+
+            // Other references stay duplicates
+            assert_error(escrow.deposit_native(2u16, accounts.charlie), Error::EscrowAccountDuplicate);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.deposit_native(1u16, accounts.charlie), Error::EscrowAccountDuplicate);
+        }
+
+        /// We test if the predicted address follows the pallet_contracts derivation.
+        #[ink::test]
+        fn predict_address_works() {
+            let deployer = AccountId::from([1u8; 32]);
+            let code_hash = Hash::from([2u8; 32]);
+            let constructor_args = [0x9B, 0xAE, 0x9D, 0x5E];
+
+            let address = crate::registry::predict_address(deployer, code_hash, &constructor_args, b"invoice-1");
+            assert_eq!(address, AccountId::from([
+                151, 250, 8, 188, 153, 221, 130, 157, 10, 230, 109, 8, 146, 155, 240, 146,
+                125, 135, 69, 189, 27, 50, 238, 180, 76, 106, 128, 147, 35, 204, 92, 234,
+            ]));
+            assert_ne!(address, crate::registry::predict_address(deployer, code_hash, &constructor_args, b"invoice-2"));
+        }
+
+        /// We test if NFT escrows share the references and only their depositor releases them.
+        #[ink::test]
+        fn nft_escrow_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            assert_error(escrow.add_nft(1u16, accounts.django, 7u32, 42u32, accounts.charlie), Error::EscrowAccountDuplicate);
+            escrow.add_nft(2u16, accounts.django, 7u32, 42u32, accounts.charlie).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowNftAdded { reference: 2, collection: 7, item: 42 })
+            );
+            assert_eq!(escrow.get_nft(2).unwrap().item, 42);
+            assert_rejected(
+                escrow.add(2u16, accounts.eve, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::EscrowAccountDuplicate,
+            );
+
+            assert_error(escrow.release_nft(2u16), Error::BadOrigin);
+            assert_error(escrow.release_nft(3u16), Error::EscrowAccountNotFound);
+        }
+
+        /// We test if a maximum of 0 accounts means unlimited and any other maximum is enforced.
+        #[ink::test]
+        fn unlimited_accounts_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 0u16);
+            assert_eq!(escrow.remaining_capacity(), None);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.account_count, 2);
+
+            // Both instances share the off-chain storage, the second one uses other
+            // depositors and references
+            let mut escrow = Escrow::new(1u128, 1u16);
+            assert_eq!(escrow.remaining_capacity(), Some(1));
+            escrow.add(3u16, accounts.eve, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.remaining_capacity(), Some(0));
+            assert_rejected(
+                escrow.add(4u16, accounts.frank, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::EscrowAccountMax,
+            );
+        }
+
+        /// We test if granted roles split the manager duties.
+        #[ink::test]
+        fn roles_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.grant_role(Role::Adder, accounts.django).unwrap();
+            escrow.grant_role(Role::Pauser, accounts.eve).unwrap();
+            assert!(escrow.has_role(Role::Releaser, accounts.alice));
+            assert!(!escrow.has_role(Role::Releaser, accounts.django));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(escrow.close(), Error::BadOrigin);
+            assert_rejected(escrow.force_release(accounts.bob, accounts.charlie, None), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.close().unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.revoke_role(Role::Adder, accounts.django).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_rejected(
+                escrow.add(2u16, accounts.frank, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::BadOrigin,
+            );
+        }
+
+        /// We test if the matured-release sweep pays the accounts in the release order.
+        #[ink::test]
+        fn sweep_matured_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 300u128, accounts.charlie, Hash::default(), None, Some(1), None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, Some(1), None).unwrap();
+            escrow.add(3u16, accounts.eve, 1u128, 200u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.release_position(1), None);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(escrow.release_position(1), Some(0));
+            escrow.set_release_order(ReleaseOrder::SmallestFirst, Vec::new()).unwrap();
+            assert_eq!(escrow.release_position(2), Some(0));
+            escrow.set_release_order(ReleaseOrder::Priority, ink::prelude::vec![2]).unwrap();
+            assert_eq!(escrow.get_release_order(), (ReleaseOrder::Priority, ink::prelude::vec![2]));
+            assert_eq!(escrow.release_position(1), Some(1));
+
+            // The unlocked account 3 is never swept, account 1 does not fit the liquidity
+            escrow.sweep_matured(350u128, 10).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowMaturedSwept { released: 1, amount: 100 }));
+            assert!(escrow.accounts.get(accounts.django).is_none());
+            assert_eq!(escrow.account_count, 2);
+        }
+
+        /// We test if a dispute freezes the account until the arbiter splits it.
+        #[ink::test]
+        fn dispute_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.grant_role(Role::Arbiter, accounts.frank).unwrap();
+
+            assert_error(escrow.raise_dispute(1u16), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.raise_dispute(1u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowDisputeRaised { reference: 1 }));
+            assert_eq!(escrow.ops_summary().disputed_accounts, 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowAccountDisputed);
+
+            // The manager does not hold the arbiter role
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_error(escrow.resolve_dispute(1u16, DisputeOutcome::Release), Error::BadOrigin);
+            assert_error(escrow.freeze(accounts.bob), Error::EscrowAccountDisputed);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            escrow.resolve_dispute(1u16, DisputeOutcome::Split(6_000)).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowDisputeResolved { reference: 1, outcome: DisputeOutcome::Split(6_000) })
+            );
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().amount, 60);
+            assert_eq!(escrow.transfer_queue.get(1).unwrap().target, accounts.bob);
+            assert_eq!(escrow.transfer_queue.get(1).unwrap().amount, 40);
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.manager_metrics.get(accounts.alice).unwrap().disputes_lost, 1);
+            assert_error(escrow.resolve_dispute(1u16, DisputeOutcome::Release), Error::EscrowDisputeNotFound);
+        }
+
+        /// We test if a dispute bond that cannot be pulled reports the failure and
+        /// leaves the account undisputed.
+        #[ink::test]
+        fn dispute_bond_failure_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_dispute_bond(10u128).unwrap();
+            escrow.set_balance_checks(true).unwrap();
+            ink::env::test::register_chain_extension(MockedTransferCheck(1));
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(escrow.raise_dispute(1u16), Err(ContractError::Runtime(RuntimeError::BalanceLow)));
+            assert_eq!(last_status(), EscrowStatus::EmitError(Error::EscrowTransferFailed));
+            assert_eq!(escrow.get_dispute(1u16), None);
+            assert_eq!(escrow.dispute_bonds, 0);
+        }
+
+        /// We test if disputes can only be raised within the dispute window.
+        #[ink::test]
+        fn dispute_window_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_default_terms(0, 1, 0).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.raise_dispute(1u16), Error::EscrowDisputeWindowClosed);
+        }
+
+        /// We test if an arbiter panel settles a dispute on the majority or the
+        /// median ruling and keeps the ruling record of its members.
+        #[ink::test]
+        fn arbiter_panel_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let arbiter = AccountId::from([7u8; 32]);
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            assert_error(escrow.set_arbiter_panel(ink::prelude::vec![accounts.eve, accounts.frank]), Error::EscrowPanelInvalid);
+            assert_error(
+                escrow.set_arbiter_panel(ink::prelude::vec![accounts.eve, accounts.eve, accounts.frank]),
+                Error::EscrowPanelInvalid,
+            );
+            assert_error(
+                escrow.set_arbiter_panel(ink::prelude::vec![accounts.alice, accounts.eve, accounts.frank]),
+                Error::EscrowPanelInvalid,
+            );
+            escrow.set_arbiter_panel(ink::prelude::vec![accounts.eve, accounts.frank, arbiter]).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.raise_dispute(1u16).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.raise_dispute(2u16).unwrap();
+
+            // The arbiter role alone no longer rules
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.grant_role(Role::Arbiter, accounts.django).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.resolve_dispute(1u16, DisputeOutcome::Refund), Error::BadOrigin);
+
+            // Majority: two of three rule a refund
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.resolve_dispute(1u16, DisputeOutcome::Refund).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowDisputeRuled { reference: 1, rulings: 1 }));
+            assert_error(escrow.resolve_dispute(1u16, DisputeOutcome::Release), Error::EscrowAlreadyRuled);
+            assert_eq!(escrow.get_rulings(1u16), ink::prelude::vec![(accounts.eve, DisputeOutcome::Refund)]);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            escrow.resolve_dispute(1u16, DisputeOutcome::Refund).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowDisputeResolved { reference: 1, outcome: DisputeOutcome::Refund })
+            );
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().target, accounts.bob);
+            assert!(escrow.get_rulings(1u16).is_empty());
+
+            // No majority: the median recipient share is executed once all ruled
+            escrow.resolve_dispute(2u16, DisputeOutcome::Release).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.resolve_dispute(2u16, DisputeOutcome::Refund).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowDisputeRuled { reference: 2, rulings: 2 }));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(arbiter);
+            escrow.resolve_dispute(2u16, DisputeOutcome::Split(4_000)).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowDisputeResolved { reference: 2, outcome: DisputeOutcome::Split(4_000) })
+            );
+            assert_eq!(escrow.account_count, 0);
+
+            assert_eq!(escrow.get_arbiter_stats(accounts.eve), ArbiterStats { rulings: 2, rewarded: 1, slashed: 1 });
+            assert_eq!(escrow.get_arbiter_stats(accounts.frank), ArbiterStats { rulings: 2, rewarded: 1, slashed: 1 });
+            assert_eq!(escrow.get_arbiter_stats(arbiter), ArbiterStats { rulings: 1, rewarded: 1, slashed: 0 });
+        }
+
+        /// We test if only the arbiter drawn from the arbiter registry rules its
+        /// dispute.
+        #[ink::test]
+        fn arbiter_registry_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let registry = AccountId::from([7u8; 32]);
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            escrow.set_arbiter_registry(Some(registry)).unwrap();
+            assert_eq!(escrow.get_arbiter_registry(), Some(registry));
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.set_arbiter_registry(None), Error::BadOrigin);
+
+            // The registry call needs a deployed contract, the drawn dispute is stored
+            // as raise_dispute records it
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_arbiter_panel(ink::prelude::vec![accounts.django, accounts.eve, accounts.frank]).unwrap();
+            escrow.grant_role(Role::Arbiter, accounts.django).unwrap();
+            let mut account = escrow.account_by_reference(1u16).unwrap();
+            account.status = 2;
+            escrow.save_account(&account);
+            escrow.disputes.insert(1u16, &Dispute { raiser: accounts.bob, bond: 0, raised_at: 0, arbiter: Some(accounts.frank) });
+
+            // Neither the arbiter role nor the rest of the panel rules a drawn dispute
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.resolve_dispute(1u16, DisputeOutcome::Refund), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_error(escrow.resolve_dispute(1u16, DisputeOutcome::Refund), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            escrow.resolve_dispute(1u16, DisputeOutcome::Release).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowDisputeResolved { reference: 1, outcome: DisputeOutcome::Release })
+            );
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().target, accounts.charlie);
+            assert_eq!(escrow.get_arbiter_stats(accounts.frank), ArbiterStats { rulings: 1, rewarded: 1, slashed: 0 });
+        }
+
+        /// We test if a batch force release pays every account and reports the
+        /// failed entries.
+        #[ink::test]
+        fn force_release_batch_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.eve, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.charlie, 1u128, 50u128, accounts.frank, Hash::default(), None, None, None).unwrap();
+
+            let outcome = escrow.force_release_batch(ink::prelude::vec![accounts.bob, accounts.django, accounts.charlie]).unwrap();
+            assert_eq!(
+                outcome.results,
+                ink::prelude::vec![
+                    BatchResult { account: accounts.bob, error: None },
+                    BatchResult { account: accounts.django, error: Some(Error::EscrowAccountNotFound) },
+                    BatchResult { account: accounts.charlie, error: None },
+                ]
+            );
+            assert_eq!(outcome.cursor, None);
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().target, accounts.eve);
+            assert_eq!(escrow.transfer_queue.get(1).unwrap().target, accounts.frank);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.force_release_batch(ink::prelude::vec![accounts.bob]), Error::BadOrigin);
+        }
+
+        /// We test if a replayed idempotency key returns the original receipt per
+        /// operator and operation.
+        #[ink::test]
+        fn idempotency_keys_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            let key_hash = Hash::from([9u8; 32]);
+            let key = Some(key_hash);
+
+            let added = escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, key).unwrap().unwrap();
+            assert_eq!(added.operation, Success::EscrowAccountAdded { reference: 1 });
+            assert_eq!(escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, key).unwrap(), Some(added.clone()));
+            assert_eq!(escrow.add_auto(accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, key).unwrap(), Some(added));
+            assert_eq!(escrow.account_count, 1);
+
+            // The same key on another operation is not a replay
+            let topped_up = escrow.top_up(accounts.bob, 10u128, key).unwrap().unwrap();
+            assert_eq!(topped_up.operation, Success::EscrowAccountToppedUp { reference: 1, amount: 10 });
+            assert_eq!(escrow.top_up(accounts.bob, 10u128, key).unwrap(), Some(topped_up));
+            assert_eq!(escrow.accounts.get(accounts.bob).unwrap().balance, 110);
+            assert_eq!(escrow.top_up(accounts.bob, 10u128, None).unwrap(), None);
+
+            // A pending override is receipted, its retry does not propose it again
+            escrow.set_dual_control(10).unwrap();
+            let proposed = escrow.force_release(accounts.bob, accounts.charlie, key).unwrap().unwrap();
+            assert_eq!(proposed.operation, Success::EscrowReleaseProposed { id: 0 });
+            assert_eq!(escrow.force_release(accounts.bob, accounts.charlie, key).unwrap(), Some(proposed));
+            assert_eq!(escrow.get_pending_release(1), None);
+            escrow.approve_release(0).unwrap();
+            let released = escrow.get_receipt(accounts.alice, KeyedOperation::ForceRelease, key_hash).unwrap();
+            assert_eq!(released.operation, Success::EscrowAccountReleased { reference: 1, amount: 120, fee: 0 });
+            assert_eq!(escrow.get_receipt(accounts.bob, KeyedOperation::ForceRelease, key_hash), None);
+        }
+
+        /// We test if an attested release keeps its receipt apart from the idempotency
+        /// keys and a document attests a single release.
+        #[ink::test]
+        fn release_with_attestation_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            let doc_hash = Hash::from([7u8; 32]);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, Some(doc_hash)).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release_with_attestation(doc_hash).unwrap();
+            let attestation = escrow.get_attestation(doc_hash).unwrap();
+            assert_eq!((attestation.reference, attestation.account, attestation.amount), (1, accounts.bob, 100));
+            assert_eq!(attestation.operation, Success::EscrowAccountReleased { reference: 1, amount: 100, fee: 0 });
+            assert_eq!(escrow.get_receipt(accounts.alice, KeyedOperation::Add, doc_hash).unwrap().operation, Success::EscrowAccountAdded { reference: 1 });
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.release_with_attestation(doc_hash), Error::EscrowAttestationUsed);
+        }
+
+        /// We test if releases pay the protocol fee to the collector.
+        #[ink::test]
+        fn fee_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            assert_error(escrow.set_fee(10_001, accounts.django), Error::EscrowFeeInvalid);
+            escrow.set_fee(250, accounts.django).unwrap();
+            assert_eq!(escrow.get_fee(), (250, accounts.django));
+
+            escrow.add(1u16, accounts.bob, 1u128, 1_000u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 1_000, fee: 25 })
+            );
+
+            let fee = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((fee.target, fee.amount), (accounts.django, 25));
+            let payout = escrow.transfer_queue.get(1).unwrap();
+            assert_eq!((payout.target, payout.amount), (accounts.charlie, 975));
+        }
+
+        /// We test if the release simulation reports the vested amount and the fee.
+        #[ink::test]
+        fn simulate_release_batch_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_fee(250, accounts.django).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 1_000u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.eve, 1u128, 1_000u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.set_vesting(2u16, 0, 10, 0).unwrap();
+            for _ in 0..4 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+
+            let results = escrow.simulate_release_batch(ink::prelude::vec![accounts.bob, accounts.eve, accounts.frank]);
+            assert_eq!(
+                results,
+                ink::prelude::vec![
+                    SimResult {
+                        account: accounts.bob,
+                        amount: 1_000,
+                        fee: 25,
+                        recipient: Some(Recipient::Local(accounts.charlie)),
+                        error: None,
+                    },
+                    SimResult {
+                        account: accounts.eve,
+                        amount: 400,
+                        fee: 10,
+                        recipient: Some(Recipient::Local(accounts.charlie)),
+                        error: None,
+                    },
+                    SimResult {
+                        account: accounts.frank,
+                        amount: 0,
+                        fee: 0,
+                        recipient: None,
+                        error: Some(Error::EscrowAccountNotFound),
+                    },
+                ]
+            );
+        }
+
+        /// We test if partial releases pay the protocol fee on each part.
+        #[ink::test]
+        fn partial_release_fee_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.set_fee(250, accounts.django).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 1_000u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release_partial(400).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountPartiallyReleased { reference: 1, amount: 400, fee: 10, remaining: 600 })
+            );
+            let fee = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((fee.target, fee.amount), (accounts.django, 10));
+            let payout = escrow.transfer_queue.get(1).unwrap();
+            assert_eq!((payout.target, payout.amount), (accounts.charlie, 390));
+
+            escrow.approve_spender(accounts.eve, 600).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.release_from(accounts.bob, 600).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 600, fee: 15 })
+            );
+            let fee = escrow.transfer_queue.get(2).unwrap();
+            assert_eq!((fee.target, fee.amount), (accounts.django, 15));
+            let payout = escrow.transfer_queue.get(3).unwrap();
+            assert_eq!((payout.target, payout.amount), (accounts.charlie, 585));
+        }
+
+        /// We test if the escrow book can be read page by page.
+        #[ink::test]
+        fn get_accounts_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(3u16, accounts.eve, 1u128, 25u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.count_accounts(), 3);
+
+            let page: Vec<u16> = escrow.get_accounts(1, 5).iter().map(|a| a.reference).collect();
+            assert_eq!(page, ink::prelude::vec![2, 3]);
+            assert_eq!(escrow.get_accounts(0, 1)[0].account, accounts.bob);
+            assert!(escrow.get_accounts(3, 5).is_empty());
+            assert!(escrow.get_accounts(u32::MAX, u32::MAX).is_empty());
+        }
+
+        /// We test if accounts can be looked up by depositor, reference and recipient.
+        #[ink::test]
+        fn lookup_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.eve, Hash::default(), None, None, None).unwrap();
+            escrow.add(3u16, accounts.frank, 1u128, 25u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            assert_eq!(escrow.get_account(accounts.bob).unwrap().reference, 1);
+            assert_eq!(escrow.get_account(accounts.alice), None);
+            assert_eq!(escrow.get_by_reference(2).unwrap().account, accounts.django);
+            assert_eq!(escrow.get_by_reference(4), None);
+
+            let references: Vec<u16> = escrow.get_by_recipient(accounts.charlie).iter().map(|a| a.reference).collect();
+            assert_eq!(references, ink::prelude::vec![1, 3]);
+            assert!(escrow.get_by_recipient(accounts.bob).is_empty());
+        }
+
+        /// We test if the per-operation events carry the account, recipient and amount.
+        #[ink::test]
+        fn operation_events_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            let added = last_event::<AccountAdded>().unwrap();
+            assert_eq!((added.account, added.recipient, added.reference, added.amount), (accounts.bob, Recipient::Local(accounts.charlie), 1, 100));
+
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.force_release_split(accounts.django, accounts.eve, 6_000, None).unwrap();
+            let forced = last_event::<ForceReleased>().unwrap();
+            assert_eq!((forced.account, forced.recipient, forced.operator), (accounts.django, accounts.eve, accounts.alice));
+            assert_eq!((forced.amount, forced.recipient_amount), (50, 30));
+
+            escrow.close().unwrap();
+            assert_eq!(last_event::<Closed>().unwrap().operator, accounts.alice);
+            escrow.open().unwrap();
+            assert_eq!(last_event::<Opened>().unwrap().operator, accounts.alice);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            let released = last_event::<Released>().unwrap();
+            assert_eq!((released.account, released.reference, released.amount), (accounts.bob, 1, 100));
+        }
+
+        /// We test if an upgrade waits for the upgrade delay.
+        #[ink::test]
+        fn upgrade_timelock_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            let code_hash = Hash::from([7u8; 32]);
+
+            assert_error(escrow.upgrade(code_hash), Error::EscrowUpgradeNotProposed);
+            assert_error(escrow.set_upgrade_delay(DEFAULT_UPGRADE_DELAY - 1), Error::EscrowUpgradeLocked);
+            escrow.set_upgrade_delay(DEFAULT_UPGRADE_DELAY + 1).unwrap();
+
+            escrow.propose_upgrade(code_hash).unwrap();
+            let execute_at = DEFAULT_UPGRADE_DELAY + 1;
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowUpgradeProposed { code_hash, execute_at }));
+            assert_eq!(escrow.get_upgrade(), (DEFAULT_UPGRADE_DELAY + 1, Some((code_hash, execute_at))));
+
+            assert_error(escrow.upgrade(code_hash), Error::EscrowUpgradeLocked);
+            assert_error(escrow.upgrade(Hash::from([8u8; 32])), Error::EscrowUpgradeNotProposed);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.propose_upgrade(code_hash), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.cancel_upgrade().unwrap();
+            assert_eq!(escrow.get_upgrade().1, None);
+            assert_error(escrow.cancel_upgrade(), Error::EscrowUpgradeNotProposed);
+        }
+
+        /// We test if a migration brings the stored state to the code layout.
+        #[ink::test]
+        fn migrate_works() {
+            let mut escrow = Escrow::new(1u128, 10u16);
+            assert_eq!(escrow.storage_version(), (STORAGE_VERSION, STORAGE_VERSION));
+
+            escrow.version = 0;
+            escrow.migrate().unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowMigrated { from: 0, to: STORAGE_VERSION }));
+            assert_eq!(escrow.version, STORAGE_VERSION);
+
+            escrow.version = STORAGE_VERSION + 1;
+            assert_error(escrow.migrate(), Error::EscrowVersionUnsupported);
+        }
+
+        /// We test if a termination waits for the queue to settle.
+        #[ink::test]
+        fn terminate_checks_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+
+            assert_error(escrow.terminate(accounts.eve), Error::EscrowQueueNotEmpty);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.terminate(accounts.eve), Error::BadOrigin);
+        }
+
+        /// We test if the pause switches halt single operations.
+        #[ink::test]
+        fn pause_switches_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            escrow.grant_role(Role::Pauser, accounts.eve).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.set_paused(PAUSE_ADD | PAUSE_FORCE_RELEASE | 0x80).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowPauseUpdated { flags: PAUSE_ADD | PAUSE_FORCE_RELEASE }));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_rejected(
+                escrow.add(3u16, accounts.frank, 1u128, 25u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::EscrowOperationPaused,
+            );
+            assert_rejected(escrow.force_release(accounts.django, accounts.charlie, None), Error::EscrowOperationPaused);
+
+            // Existing recipients are still paid
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            escrow.set_paused(PAUSE_RELEASE).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_rejected(escrow.release(), Error::EscrowOperationPaused);
+        }
+
+        /// We test if the allow-list restricts who can enter the escrow.
+        #[ink::test]
+        fn allow_list_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            assert!(escrow.is_allowed(accounts.bob));
+
+            escrow.set_allow_list_enabled(true).unwrap();
+            escrow.set_allowed(accounts.bob, true).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAllowListUpdated { account: accounts.bob, allowed: true })
+            );
+            assert!(!escrow.is_allowed(accounts.django));
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(
+                escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::EscrowDepositorNotAllowed,
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.register_intent(2u16, 50u128, accounts.charlie), Error::EscrowDepositorNotAllowed);
+            assert_error(escrow.set_allowed(accounts.django, true), Error::BadOrigin);
+        }
+
+        /// We test if funds are never sent to a blocked recipient.
+        #[ink::test]
+        fn recipient_block_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            escrow.set_recipient_blocked(accounts.charlie, true).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowRecipientBlockUpdated { recipient: accounts.charlie, blocked: true })
+            );
+            assert_rejected(
+                escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::RecipientBlocked,
+            );
+            assert_rejected(escrow.force_release(accounts.bob, accounts.charlie, None), Error::RecipientBlocked);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::RecipientBlocked);
+            assert_error(escrow.set_recipient_blocked(accounts.charlie, false), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_recipient_blocked(accounts.charlie, false).unwrap();
+            assert!(!escrow.is_recipient_blocked(accounts.charlie));
+        }
+
+        /// We test if a payout address cannot route funds around the blocklist.
+        #[ink::test]
+        fn blocked_payout_address_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            // The redirection set before the block is caught at release
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.set_payout_address(accounts.eve).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_recipient_blocked(accounts.eve, true).unwrap();
+            assert_rejected(escrow.force_release(accounts.bob, accounts.charlie, None), Error::RecipientBlocked);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::RecipientBlocked);
+
+            // A new redirection to a blocked address is rejected, clearing it is not
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_error(escrow.set_payout_address(accounts.eve), Error::RecipientBlocked);
+            escrow.set_payout_address(accounts.charlie).unwrap();
+            assert_eq!(escrow.payout_address(accounts.charlie), accounts.charlie);
+            assert_eq!(escrow.simulate_release_batch(vec![accounts.bob])[0].error, None);
+        }
+
+        /// We test if the depositor can correct its recipient before release.
+        #[ink::test]
+        fn depositor_set_recipient_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            // Only the depositor and the manager can change the recipient
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_error(escrow.set_recipient(1u16, Recipient::Local(accounts.charlie)), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.set_recipient(1u16, Recipient::Local(accounts.django)).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowRecipientSet {
+                    reference: 1u16,
+                    old: Recipient::Local(accounts.charlie),
+                    new: Recipient::Local(accounts.django),
+                })
+            );
+            assert_eq!(escrow.get_account(accounts.bob).unwrap().recipient, Recipient::Local(accounts.django));
+            assert_eq!(escrow.get_account_history(1u16).last().unwrap().activity, Activity::RecipientChanged);
+
+            // Remote recipients are vouched for by the manager
+            let remote = Recipient::Remote { dest: vec![1u8], beneficiary: vec![2u8] };
+            assert_error(escrow.set_recipient(1u16, remote.clone()), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_recipient(1u16, remote.clone()).unwrap();
+            assert_eq!(escrow.get_account(accounts.bob).unwrap().recipient, remote);
+        }
+
+        /// We test if a release signature is checked against the account and nonce.
+        #[ink::test]
+        fn release_with_sig_checks_signature() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.get_release_nonce(accounts.bob), 0u64);
+            assert_ne!(escrow.get_release_message(accounts.bob, 0u64), escrow.get_release_message(accounts.bob, 1u64));
+
+            // Relayed by django
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.release_with_sig(accounts.bob, 1u64, [0u8; 65]), Error::EscrowNonceInvalid);
+            assert_error(escrow.release_with_sig(accounts.bob, 0u64, [0u8; 65]), Error::EscrowSignatureInvalid);
+            assert_eq!(escrow.get_release_nonce(accounts.bob), 0u64);
+            assert!(escrow.get_account(accounts.bob).is_some());
+        }
+
+        /// We test if a vesting account releases its vested part only.
+        #[ink::test]
+        fn vesting_release_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            assert_error(escrow.set_vesting(1u16, 0, 10, 11), Error::EscrowVestingInvalid);
+            escrow.set_vesting(1u16, 0, 10, 2).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowVestingSet { reference: 1 }));
+
+            // Nothing vests before the cliff
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowNotVested);
+
+            // Half way the vested half is paid, the rest stays
+            for _ in 1..5 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            assert_eq!(escrow.get_vested(1u16), 50);
+            assert_error(escrow.release_partial(51u128), Error::EscrowNotVested);
+            escrow.release().unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountPartiallyReleased { reference: 1, amount: 50, fee: 0, remaining: 50 })
+            );
+            assert_eq!(escrow.get_vesting(1u16).unwrap().released, 50);
+            assert_rejected(escrow.release(), Error::EscrowNotVested);
+
+            // Once fully vested the account is released and removed
+            for _ in 5..10 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            escrow.release().unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 50, fee: 0 }));
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.get_vesting(1u16), None);
+        }
+
+        /// We test if a mutual-consent account is released once both parties approved.
+        #[ink::test]
+        fn mutual_release_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.set_mutual_release(1u16, true).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowMutualReleaseSet { reference: 1, enabled: true }));
+
+            // The depositor alone cannot release
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.consent_release(1u16).unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowReleaseConsented { reference: 1, approver: accounts.bob })
+            );
+            assert_rejected(escrow.release(), Error::EscrowReleaseNotApproved);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.consent_release(1u16), Error::BadOrigin);
+
+            // The recipient approval releases the account
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.consent_release(1u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 100, fee: 0 }));
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.get_release_consent(1u16), None);
+        }
+
+        /// We test if accounts wait for the recipient acceptance and can be cancelled
+        /// until then.
+        #[ink::test]
+        fn recipient_acceptance_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.set_recipient_acceptance(true).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.ops_summary().pending_accounts, 2);
+            assert_error(escrow.freeze(accounts.bob), Error::EscrowAccountPending);
+
+            // Pending accounts cannot be released
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowAccountPending);
+            assert_error(escrow.accept(1u16), Error::EscrowAccountNotFound);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.accept(1u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountAccepted { reference: 1 }));
+
+            // Accepted accounts are no longer cancellable, pending ones are
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.cancel(1u16), Error::EscrowAccountNotFound);
+            escrow.release().unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.cancel(2u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountCancelled { reference: 2, amount: 50 }));
+            assert_eq!(escrow.account_count, 0);
+        }
+
+        /// Assets chain extension answering every balance query with a fixed balance
+        struct MockedAssets(u128);
+
+        impl ink::env::test::ChainExtension for MockedAssets {
+            fn ext_id(&self) -> u16 {
+                0x4153
+            }
+
+            fn call(&mut self, _func_id: u16, _input: &[u8], output: &mut Vec<u8>) -> u32 {
+                scale::Encode::encode_to(&self.0, output);
+                0
+            }
+        }
+
+        /// Off-chain assets chain extension holding any balance and answering every
+        /// transfer check with a failure code.
+        struct MockedTransferCheck(u8);
+
+        impl ink::env::test::ChainExtension for MockedTransferCheck {
+            fn ext_id(&self) -> u16 {
+                0x4153
+            }
+
+            fn call(&mut self, func_id: u16, _input: &[u8], output: &mut Vec<u8>) -> u32 {
+                match func_id {
+                    1 => scale::Encode::encode_to(&u128::MAX, output),
+                    _ => scale::Encode::encode_to(&self.0, output),
+                }
+                0
+            }
+        }
+
+        /// We test if a transfer failing its check reports the reason without being
+        /// dispatched, and stays queued.
+        #[ink::test]
+        fn transfer_check_failure_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_balance_checks(true).unwrap();
+            ink::env::test::register_chain_extension(MockedTransferCheck(3));
+            escrow.flush(1).unwrap();
+
+            let failed = last_event::<TransferFailed>().unwrap();
+            assert_eq!((failed.target, failed.amount, failed.error), (accounts.charlie, 100, RuntimeError::AssetFrozen));
+            let pending = escrow.transfer_queue.get(1).unwrap();
+            assert_eq!((pending.target, pending.attempts), (accounts.charlie, 1));
+        }
+
+        /// We test if releases check the asset balance read through the chain
+        /// extension.
+        #[ink::test]
+        fn balance_checks_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.escrow_funds(1u128), None);
+
+            escrow.set_balance_checks(true).unwrap();
+            ink::env::test::register_chain_extension(MockedAssets(99));
+            assert_eq!(escrow.escrow_funds(1u128), Some(99));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::InsufficientEscrowFunds);
+
+            ink::env::test::register_chain_extension(MockedAssets(100));
+            escrow.release().unwrap();
+        }
+
+        /// We test if reconciliation reports the solvency and pauses adds when
+        /// insolvent.
+        #[ink::test]
+        fn reconcile_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_error(escrow.reconcile(), Error::EscrowBalanceUnavailable);
+
+            escrow.set_balance_checks(true).unwrap();
+            escrow.set_reconcile_pauses_adds(true).unwrap();
+            ink::env::test::register_chain_extension(MockedAssets(130));
+            escrow.reconcile().unwrap();
+            assert_eq!(last_event::<Solvent>().unwrap().surplus, 30);
+            assert!(!escrow.is_paused(PAUSE_ADD));
+
+            assert!(!escrow.insolvency_paused);
+
+            ink::env::test::register_chain_extension(MockedAssets(60));
+            escrow.reconcile().unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowPauseUpdated { flags: PAUSE_ADD }));
+            assert_eq!(last_event::<Insolvent>().unwrap().deficit, 40);
+            let detected = last_event::<InsolvencyDetected>().unwrap();
+            assert_eq!((detected.liabilities, detected.balance), (100, 60));
+            assert!(escrow.insolvency_paused);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowInsolvencyPaused);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_rejected(
+                escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None),
+                Error::EscrowOperationPaused,
+            );
+        }
+
+        /// We test if the solvency check reads the escrow asset balance and pauses
+        /// releases when insolvent.
+        #[ink::test]
+        fn check_solvency_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_error(escrow.check_solvency(), Error::EscrowBalanceUnavailable);
+
+            escrow.set_balance_checks(true).unwrap();
+            ink::env::test::register_chain_extension(MockedAssets(100));
+            escrow.check_solvency().unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowSolvent));
+            assert!(!escrow.insolvency_paused);
+
+            ink::env::test::register_chain_extension(MockedAssets(60));
+            escrow.check_solvency().unwrap();
+            let detected = last_event::<InsolvencyDetected>().unwrap();
+            assert_eq!((detected.liabilities, detected.balance), (100, 60));
+            assert!(escrow.insolvency_paused);
 
-        /// We test if the default constructor does its job.
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.check_solvency(), Error::BadOrigin);
+            assert_rejected(escrow.release(), Error::EscrowInsolvencyPaused);
+        }
+
+        /// We test if only the owner chooses the transfer method.
         #[ink::test]
-        fn default_works() {
-            let Escrow = Escrow::default();
+        fn transfer_method_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            assert_eq!(escrow.get_transfer_method(), TransferMethod::Transfer);
+
+            escrow.set_transfer_method(TransferMethod::KeepAlive).unwrap();
+            assert_eq!(escrow.get_transfer_method(), TransferMethod::KeepAlive);
+
+            // Only a payout of the whole balance sweeps the asset account
+            escrow.set_transfer_method(TransferMethod::TransferAll).unwrap();
+            escrow.set_balance_checks(true).unwrap();
+            ink::env::test::register_chain_extension(MockedAssets(100));
+            let transfer_all = RuntimeCall::Assets(AssetsCall::TransferAll {
+                id: 1,
+                dest: accounts.charlie.into(),
+                keep_alive: false,
+            });
+            let keep_alive = RuntimeCall::Assets(AssetsCall::TransferKeepAlive {
+                id: 1,
+                target: accounts.charlie.into(),
+                amount: 40,
+            });
+            assert_eq!(
+                scale::Encode::encode(&escrow.assets_transfer(1u128, accounts.charlie, 100u128)),
+                scale::Encode::encode(&transfer_all)
+            );
+            assert_eq!(
+                scale::Encode::encode(&escrow.assets_transfer(1u128, accounts.charlie, 40u128)),
+                scale::Encode::encode(&keep_alive)
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.set_transfer_method(TransferMethod::Transfer), Error::BadOrigin);
+        }
+
+        /// We test if runtime calls are addressed with the configured pallet and call
+        /// indices, nested calls included.
+        #[ink::test]
+        fn pallet_indices_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let transfer = RuntimeCall::Assets(AssetsCall::Transfer {
+                id: 1,
+                target: accounts.bob.into(),
+                amount: 100,
+            });
+
+            // The default layout encodes as declared
+            let default = IndexedCall { call: &transfer, indices: PalletIndices::default() };
+            assert_eq!(scale::Encode::encode(&default), scale::Encode::encode(&transfer));
+
+            let indices = PalletIndices { assets: 12, utility: 11, ..PalletIndices::default() };
+            let escrow = Escrow::new_for_runtime(1u128, 10u16, FEATURE_ALL, indices);
+            assert_eq!(escrow.get_pallet_indices(), indices);
+
+            let batch = RuntimeCall::Utility(UtilityCall::BatchAll { calls: vec![transfer] });
+            let encoded = scale::Encode::encode(&IndexedCall { call: &batch, indices });
+            assert_eq!(&encoded[..5], &[11u8, 2, 4, 12, 8][..]);
+            assert_eq!(encoded.len(), scale::Encode::encode(&batch).len());
+
+            // Call indices move with the pallet version, the arguments stay as declared
+            let calls = CallIndices { assets_transfer: 5, utility_batch_all: 3, ..CallIndices::default() };
+            let indices = PalletIndices { calls, ..indices };
+            let reindexed = scale::Encode::encode(&IndexedCall { call: &batch, indices });
+            assert_eq!(&reindexed[..5], &[11u8, 3, 4, 12, 5][..]);
+            assert_eq!(&reindexed[5..], &encoded[5..]);
+        }
+
+        /// We test if accounts are released by their reference.
+        #[ink::test]
+        fn release_by_reference_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_error(escrow.force_release_by_reference(3u16), Error::EscrowAccountNotFound);
+
+            // Depositors only release their own reference
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.release_by_reference(2u16), Error::EscrowAccountNotFound);
+            assert_error(escrow.force_release_by_reference(2u16), Error::BadOrigin);
+            escrow.release_by_reference(1u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1, amount: 100, fee: 0 }));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.force_release_by_reference(2u16).unwrap();
+            assert_eq!(escrow.account_count, 0);
+        }
+
+        /// We test if the granular setters keep the escrow accounts.
+        #[ink::test]
+        fn granular_setters_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            escrow.set_maximum_accounts(20u16).unwrap();
+            assert_eq!(escrow.maximum_accounts, 20);
+            assert_error(escrow.set_maximum_accounts(1u16), Error::EscrowAccountMax);
+            escrow.set_maximum_accounts(0u16).unwrap();
+            assert_error(escrow.set_asset_id(2u128), Error::EscrowIsNotEmpty);
+            escrow.set_manager(accounts.eve).unwrap();
+            assert_eq!(escrow.account_count, 2);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.set_maximum_accounts(5u16), Error::BadOrigin);
+            assert_error(escrow.set_asset_id(2u128), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            let mut empty = Escrow::new(1u128, 10u16);
+            empty.set_asset_id(2u128).unwrap();
+            assert_eq!(empty.asset_id, 2);
+        }
+
+        /// We test if a non-manager add returns BadOrigin.
+        #[ink::test]
+        fn add_by_non_manager_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None), Error::BadOrigin);
+            assert_eq!(escrow.account_count, 0);
+        }
+
+        /// We test if adding to a closed escrow returns EscrowIsClose.
+        #[ink::test]
+        fn add_when_closed_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.close().unwrap();
+            assert_rejected(escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None), Error::EscrowIsClose);
+        }
+
+        /// We test if a second add for the same account returns EscrowAccountDuplicate.
+        #[ink::test]
+        fn add_duplicate_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(escrow.add(2u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None), Error::EscrowAccountDuplicate);
+        }
+
+        /// We test if adding beyond capacity returns EscrowAccountMax.
+        #[ink::test]
+        fn add_beyond_capacity_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 1u16);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None), Error::EscrowAccountMax);
+            assert_eq!(escrow.account_count, 1);
+        }
+
+        /// We test if opening an under-collateralized escrow returns EscrowBondInsufficient.
+        #[ink::test]
+        fn open_without_bond_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.setup(1u128, accounts.alice, 10u16, 1_000u128, 0u8, 10, 10, false).unwrap();
+            assert_eq!(escrow.status, 1);
+            assert_error(escrow.open(), Error::EscrowBondInsufficient);
+        }
+
+        /// We test if the owner and the arbiters can slash the manager bond.
+        #[ink::test]
+        fn slash_bond_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.bond = 100;
+            escrow.grant_role(Role::Arbiter, accounts.frank).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.slash_bond(10u128, accounts.bob), Error::BadOrigin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            assert_error(escrow.slash_bond(101u128, accounts.eve), Error::EscrowBondInsufficient);
+            escrow.slash_bond(30u128, accounts.eve).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowBondSlashed { amount: 30 }));
+            assert_eq!(escrow.bond, 70);
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().target, accounts.eve);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.slash_bond(70u128, accounts.eve).unwrap();
+            assert_eq!(escrow.bond, 0);
+        }
+
+        /// We test if releasing without an account returns EscrowAccountNotFound.
+        #[ink::test]
+        fn release_without_account_fails() {
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            assert_rejected(escrow.release(), Error::EscrowAccountNotFound);
+        }
+
+        /// We test if releasing to an unconfirmed recipient returns EscrowRecipientNotConfirmed.
+        #[ink::test]
+        fn release_unconfirmed_fails() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            escrow.set_recipient_confirmation(true).unwrap();
+            escrow.add(1u16, accounts.alice, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_rejected(escrow.release(), Error::EscrowRecipientNotConfirmed);
+        }
+
+        /// We test if refunds are locked while open and right after closing.
+        #[ink::test]
+        fn refund_when_closed_preconditions() {
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            assert_error(escrow.refund_when_closed(), Error::EscrowIsOpen);
+
+            escrow.close().unwrap();
+            assert_error(escrow.refund_when_closed(), Error::EscrowRefundLocked);
+        }
+
+        /// We test if the close policy decides whether a close blocks adds, releases
+        /// or both.
+        #[ink::test]
+        fn close_policy_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            // Adds only: depositors keep releasing while closed
+            escrow.setup(1u128, accounts.alice, 10u16, 0u128, 1u8, 10, 10, false).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.close().unwrap();
+            assert_rejected(escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None), Error::EscrowIsClose);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            assert_eq!(escrow.account_count, 0);
+
+            // Releases only: the manager keeps adding while closed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.setup(1u128, accounts.alice, 10u16, 0u128, 2u8, 10, 10, false).unwrap();
+            escrow.close().unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_rejected(escrow.release(), Error::EscrowIsClose);
+            assert_eq!(escrow.account_count, 1);
+        }
+
+        /// We test if depositors reclaim their balance once the escrow has been
+        /// closed for longer than the refund delay.
+        #[ink::test]
+        fn refund_when_closed_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.setup(1u128, accounts.alice, 10u16, 0u128, 0u8, 2, 10, false).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.close().unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_error(escrow.refund_when_closed(), Error::EscrowRefundLocked);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            escrow.refund_when_closed().unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountRefunded { reference: 1, amount: 100 })
+            );
+            let refund = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((refund.target, refund.amount), (accounts.bob, 100));
+            assert_eq!(escrow.account_count, 0);
+            assert_error(escrow.refund_when_closed(), Error::EscrowAccountNotFound);
+        }
+
+        /// We test if only the recipient confirms its address and the account is
+        /// then releasable.
+        #[ink::test]
+        fn recipient_confirmation_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.set_recipient_confirmation(true).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowRecipientNotConfirmed);
+            assert_error(escrow.confirm_recipient(1u16), Error::EscrowAccountNotFound);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert_error(escrow.confirm_recipient(2u16), Error::EscrowAccountNotFound);
+            escrow.confirm_recipient(1u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowRecipientConfirmed { reference: 1 }));
+            assert!(escrow.accounts.get(accounts.bob).unwrap().confirmed);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().target, accounts.charlie);
+        }
+
+        /// We test if the manager tags accounts within the bounds and accounts are
+        /// filtered by tag.
+        #[ink::test]
+        fn tags_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            assert_error(escrow.add_tag(1u16, String::new()), Error::EscrowTagInvalid);
+            assert_error(escrow.add_tag(1u16, "x".repeat(MAXIMUM_TAG_LENGTH + 1)), Error::EscrowTagInvalid);
+            assert_error(escrow.add_tag(3u16, String::from("payroll")), Error::EscrowAccountNotFound);
+
+            escrow.add_tag(1u16, String::from("payroll")).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowTagAdded { reference: 1 }));
+            escrow.add_tag(2u16, String::from("payroll")).unwrap();
+            escrow.add_tag(2u16, String::from("payroll")).unwrap();
+            for tag in ["a", "b", "c"] {
+                escrow.add_tag(2u16, String::from(tag)).unwrap();
+            }
+            assert_error(escrow.add_tag(2u16, String::from("d")), Error::EscrowTagMax);
+
+            let tagged = escrow.get_accounts_by_tag(String::from("payroll"), 0, 10);
+            assert_eq!(tagged.iter().map(|a| a.reference).collect::<Vec<_>>(), vec![1, 2]);
+            assert_eq!(escrow.get_accounts_by_tag(String::from("payroll"), 1, 10).len(), 1);
+
+            escrow.remove_tag(1u16, String::from("payroll")).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowTagRemoved { reference: 1 }));
+            assert_eq!(escrow.get_accounts_by_tag(String::from("payroll"), 0, 10)[0].reference, 2);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.add_tag(1u16, String::from("vendor")), Error::BadOrigin);
+            assert_error(escrow.remove_tag(2u16, String::from("a")), Error::BadOrigin);
+        }
+
+        /// We test if accounts referencing a payout template are released along its
+        /// current shares.
+        #[ink::test]
+        fn payout_templates_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            let half = |recipient| PayoutShare { recipient, bps: 5_000 };
+
+            assert_error(escrow.set_template(1u16, String::from("split"), vec![half(accounts.eve)]), Error::EscrowTemplateInvalid);
+            assert_error(escrow.set_template(1u16, String::from("split"), Vec::new()), Error::EscrowTemplateInvalid);
+            escrow.set_template(1u16, String::from("split"), vec![half(accounts.eve), half(accounts.frank)]).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowTemplateSaved { id: 1 }));
+            assert_eq!(escrow.get_template(1u16).unwrap().name, String::from("split"));
+
+            assert_rejected(
+                escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), Some(2), None, None),
+                Error::EscrowTemplateNotFound,
+            );
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), Some(1), None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 100u128, accounts.charlie, Hash::default(), Some(1), None, None).unwrap();
+
+            // Updating the template applies to the accounts not yet released
+            let shares = vec![PayoutShare { recipient: accounts.eve, bps: 7_000 }, PayoutShare { recipient: accounts.frank, bps: 3_000 }];
+            escrow.set_template(1u16, String::from("split"), shares).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            let first = escrow.transfer_queue.get(0).unwrap();
+            let second = escrow.transfer_queue.get(1).unwrap();
+            assert_eq!((first.target, first.amount), (accounts.eve, 70));
+            assert_eq!((second.target, second.amount), (accounts.frank, 30));
+
+            // A removed template holds its accounts until it is saved again
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.remove_template(1u16).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowTemplateRemoved { id: 1 }));
+            assert_eq!(escrow.get_template(1u16), None);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_rejected(escrow.release(), Error::EscrowTemplateNotFound);
+            assert_error(escrow.remove_template(1u16), Error::BadOrigin);
+        }
+
+        /// We test if a renamed message answers like its legacy wrapper, which keeps
+        /// the selector derived from the original name.
+        #[ink::test]
+        fn legacy_selectors_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let escrow = Escrow::new(1u128, 10u16);
+
+            assert_eq!(escrow.info(), (1u128, accounts.alice, accounts.alice, 10u16, 0u8));
+            assert_eq!(escrow.get(), escrow.info());
+            assert_eq!(ink::selector_bytes!("get"), [0x2F, 0x86, 0x5B, 0xD9]);
+            assert_eq!(ink::selector_bytes!("info"), [0x01, 0x90, 0xE5, 0xAC]);
+        }
+
+        /// We test if a refund returns the balance pro-rata to the depositor and the
+        /// third-party contributors.
+        #[ink::test]
+        fn contribution_refunds_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            // Funding pulls through the runtime, its bookkeeping is replayed here
+            let mut account = escrow.accounts.get(accounts.bob).unwrap();
+            account.balance = 400;
+            escrow.save_account(&account);
+            escrow.credit(1u128, 300);
+            escrow.contributions.insert(1u16, &vec![
+                Contribution { contributor: accounts.django, amount: 200 },
+                Contribution { contributor: accounts.eve, amount: 100 },
+            ]);
+            assert_eq!(
+                escrow.get_contributions(1u16),
+                vec![
+                    Contribution { contributor: accounts.bob, amount: 100 },
+                    Contribution { contributor: accounts.django, amount: 200 },
+                    Contribution { contributor: accounts.eve, amount: 100 },
+                ]
+            );
+
+            escrow.refund(accounts.bob).unwrap();
+            let refunds: Vec<(AccountId, u128)> = (0..3)
+                .map(|n| escrow.transfer_queue.get(n).unwrap())
+                .map(|p| (p.target, p.amount))
+                .collect();
+            assert_eq!(refunds, vec![(accounts.bob, 100), (accounts.django, 200), (accounts.eve, 100)]);
+            assert_eq!(escrow.account_count, 0);
+        }
+
+
+        /// We test if positions can only move between escrows the manager accepts and
+        /// if a position is checked before it leaves.
+        #[ink::test]
+        fn escrow_transfer_checks_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            // Only the manager migrates positions and accepts escrows
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.transfer_to_escrow(1u16, accounts.django), Error::BadOrigin);
+            assert_error(escrow.set_accepted_escrow(accounts.django, true), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+
+            assert_error(escrow.transfer_to_escrow(2u16, accounts.django), Error::EscrowAccountNotFound);
+
+            let mut account = escrow.accounts.get(accounts.bob).unwrap();
+            account.status = 2;
+            escrow.save_account(&account);
+            assert_error(escrow.transfer_to_escrow(1u16, accounts.django), Error::EscrowAccountDisputed);
+
+            account.status = 1;
+            account.recipient = Recipient::Remote { dest: vec![1u8], beneficiary: vec![2u8] };
+            escrow.save_account(&account);
+            assert_error(escrow.transfer_to_escrow(1u16, accounts.django), Error::EscrowRecipientUnsupported);
+
+            // Only accepted escrows can transfer positions in
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                escrow.receive_position(1u128, 1u16, accounts.eve, 100u128, accounts.charlie),
+                Err(ContractError::Internal(Error::BadOrigin))
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_accepted_escrow(accounts.django, true).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowConfigUpdated));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                escrow.receive_position(1u128, 1u16, accounts.eve, 100u128, accounts.charlie),
+                Err(ContractError::Internal(Error::EscrowAccountDuplicate))
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_accepted_escrow(accounts.django, false).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_eq!(
+                escrow.receive_position(1u128, 2u16, accounts.eve, 100u128, accounts.charlie),
+                Err(ContractError::Internal(Error::BadOrigin))
+            );
+        }
+
+
+        /// We test if the owner can rescue foreign assets but never the escrowed ones.
+        #[ink::test]
+        fn rescue_asset_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.rescue_asset(3, 10, accounts.eve), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+
+            assert_error(escrow.rescue_asset(1, 10, accounts.eve), Error::EscrowAssetProtected);
+
+            escrow.rescue_asset(3, 10, accounts.eve).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAssetRescued { asset_id: 3, amount: 10 }));
+            let pending = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((pending.asset_id, pending.target, pending.amount, pending.reference), (3, accounts.eve, 10, None));
+        }
+
+
+        /// We test if the storage funds are kept apart and only the native excess can
+        /// be withdrawn.
+        #[ink::test]
+        fn native_balance_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            let contract = ink::env::account_id::<ink::env::DefaultEnvironment>();
+            let minimum = ink::env::minimum_balance::<ink::env::DefaultEnvironment>();
+
+            // The transferred value is credited to the escrow by the runtime
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(100);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(contract, minimum + 150);
+            escrow.fund_storage().unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowStorageFunded { amount: 100 }));
+            assert_eq!(escrow.get_native_balance(), (minimum + 150, 100, 50));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.withdraw_native(50, accounts.eve), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+
+            assert_error(escrow.withdraw_native(51, accounts.eve), Error::EscrowNativeInsufficient);
+            escrow.withdraw_native(50, accounts.eve).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowNativeWithdrawn { amount: 50 }));
+            assert_eq!(escrow.get_native_balance(), (minimum + 100, 100, 0));
+        }
+
+
+        /// We test if the queued transfers are paginated from the head of the queue.
+        #[ink::test]
+        fn get_queued_transfers_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            for amount in 1..=4u128 {
+                escrow.rescue_asset(3, amount, accounts.eve).unwrap();
+            }
+
+            let amounts = |transfers: Vec<PendingTransfer>| -> Vec<u128> {
+                transfers.iter().map(|p| p.amount).collect()
+            };
+            assert_eq!(amounts(escrow.get_queued_transfers(0, 10)), vec![1, 2, 3, 4]);
+            assert_eq!(amounts(escrow.get_queued_transfers(1, 2)), vec![2, 3]);
+            assert_eq!(amounts(escrow.get_queued_transfers(4, 2)), Vec::<u128>::new());
+
+            // Processed transfers leave from the head
+            escrow.queue_head = 2;
+            assert_eq!(amounts(escrow.get_queued_transfers(0, 10)), vec![3, 4]);
+            assert_eq!(amounts(escrow.get_queued_transfers(1, u32::MAX)), vec![4]);
+        }
+
+
+        /// We test if the bookkeeping invariants hold after a message.
+        #[cfg(feature = "debug-assertions")]
+        #[ink::test]
+        fn invariants_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            escrow.assert_invariants();
+        }
+
+        /// We test if a message panics, reverting the call, on broken bookkeeping.
+        #[cfg(feature = "debug-assertions")]
+        #[ink::test]
+        #[should_panic(expected = "total balance mismatch")]
+        fn broken_invariants_panic() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.total_balance = 90;
+            escrow.set_recipient_confirmation(false).unwrap();
+        }
+
+
+        /// We test if the funding tx-hash is stored with the account and emitted.
+        #[ink::test]
+        fn funding_tx_hash_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            let tx_hash = Hash::from([9u8; 32]);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, tx_hash, None, None, None).unwrap();
+            assert_eq!(escrow.get_account(accounts.bob).unwrap().tx_hash, tx_hash);
+            let committed = last_event::<FundingCommitted>().unwrap();
+            assert_eq!(
+                (committed.account, committed.reference, committed.amount, committed.tx_hash),
+                (accounts.bob, 1u16, 100u128, tx_hash)
+            );
+
+            let tx_hash = Hash::from([8u8; 32]);
+            escrow.add_auto(accounts.django, 1u128, 50u128, accounts.charlie, tx_hash, None, None, None).unwrap();
+            assert_eq!(escrow.get_account(accounts.django).unwrap().tx_hash, tx_hash);
+            assert_eq!(last_event::<FundingCommitted>().unwrap().tx_hash, tx_hash);
+        }
+
+
+        /// We test if operations on an account carrying a correlation id emit its
+        /// account event.
+        #[ink::test]
+        fn correlation_id_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            let account_events = || {
+                let signature = <AccountEvent as ink::env::Event>::SIGNATURE_TOPIC.unwrap();
+                ink::env::test::recorded_events()
+                    .filter(|e| e.topics.first().map(|t| t.as_slice()) == Some(&signature[..]))
+                    .count()
+            };
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.set_correlation_id(1u16, Some(Hash::from([5u8; 32]))), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_error(escrow.set_correlation_id(2u16, Some(Hash::from([5u8; 32]))), Error::EscrowAccountNotFound);
+
+            let correlation_id = Hash::from([5u8; 32]);
+            escrow.set_correlation_id(1u16, Some(correlation_id)).unwrap();
+            assert_eq!(escrow.get_account(accounts.bob).unwrap().correlation_id, Some(correlation_id));
+            let event = last_event::<AccountEvent>().unwrap();
+            assert_eq!(event.correlation_id, correlation_id);
+            assert_eq!(event.status, EscrowStatus::EmitSuccess(Success::EscrowCorrelationSet { reference: 1u16 }));
+
+            escrow.add_tag(1u16, String::from("invoice")).unwrap();
+            assert_eq!(
+                last_event::<AccountEvent>().unwrap().status,
+                EscrowStatus::EmitSuccess(Success::EscrowTagAdded { reference: 1u16 })
+            );
+
+            // Clearing the correlation id stops the account events
+            escrow.set_correlation_id(1u16, None).unwrap();
+            let emitted = account_events();
+            escrow.add_tag(1u16, String::from("paid")).unwrap();
+            assert_eq!(account_events(), emitted);
+        }
+
+
+        /// We test if the escrow events carry the reference and amount of the operation.
+        #[ink::test]
+        fn success_payloads_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountAdded { reference: 1u16 }));
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountAdded { reference: 2u16 }));
+
+            // The account event carries the same operation
+            escrow.set_correlation_id(1u16, Some(Hash::from([5u8; 32]))).unwrap();
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            let released = EscrowStatus::EmitSuccess(Success::EscrowAccountReleased { reference: 1u16, amount: 100u128, fee: 0 });
+            assert_eq!(last_status(), released);
+            assert_eq!(last_event::<AccountEvent>().unwrap().status, released);
+
+            escrow.refund(accounts.django).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowAccountRefunded { reference: 2u16, amount: 50u128 }));
+
+            escrow.flush(0).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowQueueFlushed { processed: 0 }));
+        }
+
+
+        /// We test if the payouts of a recipient follow its payout address.
+        #[ink::test]
+        fn payout_address_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.payout_address(accounts.charlie), accounts.charlie);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.set_payout_address(accounts.frank).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowPayoutAddressSet));
+            assert_eq!(escrow.payout_address(accounts.charlie), accounts.frank);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.release().unwrap();
+            assert_eq!(escrow.transfer_queue.get(0).unwrap().target, accounts.frank);
+
+            // Setting its own address clears the redirection
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            escrow.set_payout_address(accounts.charlie).unwrap();
+            assert_eq!(escrow.payout_addresses.get(accounts.charlie), None);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            escrow.release().unwrap();
+            assert_eq!(escrow.transfer_queue.get(1).unwrap().target, accounts.charlie);
+        }
+
+
+        /// We test if the manager notes are only readable by the auditor and the owner.
+        #[ink::test]
+        fn notes_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.add_note(1u16, String::from("kyc ok")), Error::BadOrigin);
+            assert_error(escrow.set_auditor(accounts.eve), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+
+            assert_error(escrow.add_note(1u16, String::new()), Error::EscrowNoteInvalid);
+            assert_error(escrow.add_note(1u16, "x".repeat(MAXIMUM_NOTE_LENGTH + 1)), Error::EscrowNoteInvalid);
+            assert_error(escrow.add_note(2u16, String::from("kyc ok")), Error::EscrowAccountNotFound);
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            escrow.add_note(1u16, String::from("kyc ok")).unwrap();
+            assert_eq!(last_status(), EscrowStatus::EmitSuccess(Success::EscrowNoteAdded { reference: 1u16 }));
+            for _ in 1..MAXIMUM_NOTES {
+                escrow.add_note(1u16, String::from("follow-up")).unwrap();
+            }
+            assert_error(escrow.add_note(1u16, String::from("follow-up")), Error::EscrowNoteMax);
+
+            // Only the owner and the auditor read the notes
+            escrow.set_auditor(accounts.eve).unwrap();
+            let notes = escrow.get_notes(1u16).unwrap();
+            assert_eq!(notes.len(), MAXIMUM_NOTES);
+            assert_eq!(notes[0], Note { author: accounts.alice, text: String::from("kyc ok"), block_number: 1 });
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.eve);
+            assert_eq!(escrow.get_notes(1u16).unwrap().len(), MAXIMUM_NOTES);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(escrow.get_notes(1u16), Err(Error::BadOrigin));
+        }
+
+
+        /// We test if the queue position of a release and its estimated block follow
+        /// the queue throughput.
+        #[ink::test]
+        fn queue_status_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 2).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(3u16, accounts.eve, 1u128, 25u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.queue_status(1u16), None);
+
+            for account in [accounts.bob, accounts.django, accounts.eve] {
+                escrow.force_release(account, accounts.charlie, None).unwrap();
+            }
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(escrow.queue_status(1u16), Some((0, 1)));
+            assert_eq!(escrow.queue_status(2u16), Some((1, 1)));
+            assert_eq!(escrow.queue_status(3u16), Some((2, 2)));
+
+            // Positions count from the head of the queue
+            escrow.queue_head = 1;
+            assert_eq!(escrow.queue_status(1u16), None);
+            assert_eq!(escrow.queue_status(3u16), Some((1, 1)));
+        }
+
+
+        /// We test if the manager counters track adds and the blocks to approval.
+        #[ink::test]
+        fn manager_metrics_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            for _ in 0..2 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            for _ in 0..4 {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            escrow.force_release(accounts.django, accounts.charlie, None).unwrap();
+
+            assert_eq!(
+                escrow.get_manager_metrics(accounts.alice).unwrap(),
+                ManagerMetrics {
+                    accounts_added: 2,
+                    releases_approved: 2,
+                    approval_blocks: 8,
+                    average_approval_blocks: 4,
+                    disputes_lost: 0,
+                }
+            );
+            assert_eq!(escrow.get_manager_metrics(accounts.django).unwrap(), ManagerMetrics::default());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(escrow.get_manager_metrics(accounts.alice), Err(Error::BadOrigin));
+        }
+
+
+        /// We test if the dispute bond goes to the winner of the dispute.
+        #[ink::test]
+        fn dispute_bond_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.grant_role(Role::Arbiter, accounts.frank).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.set_dispute_bond(10u128), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.set_dispute_bond(10u128).unwrap();
+            assert_eq!(escrow.dispute_bond, 10);
+
+            // Pulling the bond goes through the runtime, its bookkeeping is replayed here
+            escrow.set_dispute_bond(0u128).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.raise_dispute(1u16).unwrap();
+            let mut dispute = escrow.get_dispute(1u16).unwrap();
+            dispute.bond = 10;
+            escrow.disputes.insert(1u16, &dispute);
+            escrow.dispute_bonds = 10;
+
+            // The depositor loses, its bond goes to the recipient
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.frank);
+            escrow.resolve_dispute(1u16, DisputeOutcome::Release).unwrap();
+            let transfers: Vec<(AccountId, u128)> = (0..2)
+                .map(|n| escrow.transfer_queue.get(n).unwrap())
+                .map(|p| (p.target, p.amount))
+                .collect();
+            assert_eq!(transfers, vec![(accounts.charlie, 100), (accounts.charlie, 10)]);
+            assert_eq!(escrow.dispute_bonds, 0);
+        }
+
+
+        /// We test if a split release pays the recipient its share and refunds the
+        /// rest to the depositor.
+        #[ink::test]
+        fn force_release_split_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_error(escrow.force_release_split(accounts.bob, accounts.charlie, 6_000, None), Error::BadOrigin);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_error(escrow.force_release_split(accounts.bob, accounts.charlie, 10_001, None), Error::EscrowSplitInvalid);
+
+            escrow.force_release_split(accounts.bob, accounts.charlie, 7_500, None).unwrap();
+            let transfers: Vec<(AccountId, u128)> = (0..2)
+                .map(|n| escrow.transfer_queue.get(n).unwrap())
+                .map(|p| (p.target, p.amount))
+                .collect();
+            assert_eq!(transfers, vec![(accounts.charlie, 75), (accounts.bob, 25)]);
+            assert_eq!(escrow.account_count, 0);
+            assert_eq!(escrow.total_balance, 0);
+        }
+
+
+        /// We test if a depositor can pause the release of its own account for the
+        /// pause duration.
+        #[ink::test]
+        fn pause_my_account_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert_error(escrow.pause_my_account(), Error::EscrowAccountNotFound);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.pause_my_account().unwrap();
+            assert_eq!(
+                last_status(),
+                EscrowStatus::EmitSuccess(Success::EscrowAccountPaused { reference: 1u16, until: ACCOUNT_PAUSE_DURATION })
+            );
+            assert_rejected(escrow.release(), Error::EscrowAccountPaused);
+            assert_error(escrow.release_partial(50u128), Error::EscrowAccountPaused);
+
+            for _ in 0..ACCOUNT_PAUSE_DURATION {
+                ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            }
+            escrow.release().unwrap();
+            assert_eq!(escrow.account_count, 0);
+        }
+
+
+        /// We test if the weight estimates cover the main messages and do not follow
+        /// the book size.
+        #[ink::test]
+        fn estimated_weights_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+
+            let estimates = escrow.estimated_weights();
+            assert_eq!(estimates.len(), MESSAGE_BASE_REF_TIME.len());
+            let release = estimates.iter().find(|e| e.message == "release").unwrap();
+            assert_eq!(release.ref_time, 4_000_000_000 + ACCOUNT_READ_REF_TIME);
+            assert_eq!(release.proof_size, MESSAGE_BASE_PROOF_SIZE + ACCOUNT_PROOF_SIZE);
+
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            assert_eq!(escrow.estimated_weights(), estimates);
+        }
+
+
+        /// We test if the liabilities of each asset are kept apart.
+        #[ink::test]
+        fn liability_of_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(2u16, accounts.django, 2u128, 50u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+            escrow.add(3u16, accounts.eve, 1u128, 30u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            // The escrow asset also covers the bonds and the dust pot
+            escrow.bond = 20;
+            escrow.dust_pot = 5;
+            escrow.dispute_bonds = 10;
+            assert_eq!(escrow.liability_of(1), 165);
+            assert_eq!(escrow.liability_of(2), 50);
+            assert_eq!(escrow.liability_of(3), 0);
+
+            // Queued transfers stay owed in their own asset
+            escrow.force_release(accounts.bob, accounts.charlie, None).unwrap();
+            escrow.force_release(accounts.django, accounts.charlie, None).unwrap();
+            escrow.rescue_asset(3, 7, accounts.frank).unwrap();
+            assert_eq!(escrow.liability_of(1), 165);
+            assert_eq!(escrow.liability_of(2), 50);
+            assert_eq!(escrow.liability_of(3), 7);
+        }
+
+
+        /// We test if EVM recipients are paid to their mapped account on this chain.
+        #[ink::test]
+        fn evm_recipient_works() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.set_deferred_transfers(true, 16).unwrap();
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            let mut mapped = [0xEEu8; 32];
+            mapped[..20].copy_from_slice(&[0x11u8; 20]);
+            assert_eq!(Recipient::Evm([0x11u8; 20]).local_account(), Some(AccountId::from(mapped)));
+            assert_eq!(Recipient::Local(accounts.charlie).local_account(), Some(accounts.charlie));
+            assert_eq!(Recipient::Remote { dest: vec![1u8], beneficiary: vec![2u8] }.local_account(), None);
+
+            // The depositor can move its own account to an EVM recipient
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            escrow.set_recipient(1u16, Recipient::Evm([0x11u8; 20])).unwrap();
+            escrow.release().unwrap();
+            let pending = escrow.transfer_queue.get(0).unwrap();
+            assert_eq!((pending.target, pending.amount), (AccountId::from(mapped), 100));
+        }
+
+
+        /// We test if the legacy messages keep returning `Ok` on a rejection, reported
+        /// by the escrow event only, while the newer messages return the error.
+        #[ink::test]
+        fn legacy_rejections_work() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut escrow = Escrow::new(1u128, 10u16);
+            escrow.add(1u16, accounts.bob, 1u128, 100u128, accounts.charlie, Hash::default(), None, None, None).unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.setup(2u128, accounts.bob, 5u16, 0u128, 0u8, 10, 10, false), Error::BadOrigin);
+            assert_eq!((escrow.asset_id, escrow.manager), (1u128, accounts.alice));
+            assert_rejected(escrow.close(), Error::BadOrigin);
+            assert_rejected(escrow.add(2u16, accounts.django, 1u128, 50u128, accounts.charlie, Hash::default(), None, None, None), Error::BadOrigin);
+            assert_rejected(escrow.force_release(accounts.bob, accounts.charlie, None), Error::BadOrigin);
+            assert_eq!(escrow.account_count, 1);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            escrow.freeze(accounts.bob).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_rejected(escrow.release(), Error::EscrowAccountFrozen);
+            assert_error(escrow.release_partial(50u128), Error::EscrowAccountFrozen);
+            assert_eq!(escrow.get_account(accounts.bob).unwrap().balance, 100);
         }
     }
 
@@ -0,0 +1,45 @@
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::AccountId;
+
+/// PSP22 error messages
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+    /// Custom error type for cases not covered by the standard
+    Custom(String),
+    /// Not enough balance to fulfill the request
+    InsufficientBalance,
+    /// Not enough allowance to fulfill the request
+    InsufficientAllowance,
+    /// Recipient address is zero
+    ZeroRecipientAddress,
+    /// Sender address is zero
+    ZeroSenderAddress,
+    /// Safe transfer check failed
+    SafeTransferCheckFailed(String),
+}
+
+/// PSP22 fungible token standard, the messages the escrow calls on a token contract
+/// holding its escrow asset
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// Returns the token balance of the owner
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u128;
+
+    /// Transfers `value` tokens from the caller to `to`
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, value: u128, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Transfers `value` tokens from `from` to `to` within the allowance of the caller
+    #[ink(message)]
+    fn transfer_from(&mut self,
+        from: AccountId,
+        to: AccountId,
+        value: u128,
+        data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Allows `spender` to withdraw up to `value` tokens of the caller
+    #[ink(message)]
+    fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error>;
+}
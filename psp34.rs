@@ -0,0 +1,74 @@
+use ink::prelude::{string::String, vec::Vec};
+use ink::primitives::AccountId;
+
+/// PSP34 token id
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub enum Id {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Bytes(Vec<u8>),
+}
+
+/// PSP34 error messages
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP34Error {
+    /// Custom error type for cases not covered by the standard
+    Custom(String),
+    /// Owner cannot approve itself
+    SelfApprove,
+    /// Caller is not allowed to transfer the token
+    NotApproved,
+    /// Token already exists
+    TokenExists,
+    /// Token does not exist
+    TokenNotExists,
+    /// Safe transfer check failed
+    SafeTransferCheckFailed(String),
+}
+
+/// PSP34 non-fungible token standard
+#[ink::trait_definition]
+pub trait PSP34 {
+    /// Returns the collection id
+    #[ink(message)]
+    fn collection_id(&self) -> Id;
+
+    /// Returns the number of tokens held by the owner
+    #[ink(message)]
+    fn balance_of(&self, owner: AccountId) -> u32;
+
+    /// Returns the owner of the token
+    #[ink(message)]
+    fn owner_of(&self, id: Id) -> Option<AccountId>;
+
+    /// Returns true if the operator is allowed to transfer the token (or all tokens
+    /// when no id is given) of the owner
+    #[ink(message)]
+    fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool;
+
+    /// Approves or disapproves the operator to transfer the token (or all tokens
+    /// when no id is given) of the caller
+    #[ink(message)]
+    fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<(), PSP34Error>;
+
+    /// Transfers the token to another account
+    #[ink(message)]
+    fn transfer(&mut self, to: AccountId, id: Id, data: Vec<u8>) -> Result<(), PSP34Error>;
+
+    /// Returns the number of tokens in circulation
+    #[ink(message)]
+    fn total_supply(&self) -> u128;
+}
+
+/// PSP34 metadata extension
+#[ink::trait_definition]
+pub trait PSP34Metadata {
+    /// Returns the SCALE encoded attribute of the token
+    #[ink(message)]
+    fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>>;
+}
@@ -0,0 +1,19 @@
+use ink::primitives::AccountId;
+
+use crate::errors::ContractError;
+
+/// Shared interface of escrow contracts that can receive positions migrated from
+/// another escrow
+#[ink::trait_definition]
+pub trait EscrowReceiver {
+    /// Receive an escrow position from the calling escrow contract. The caller must
+    /// have approved the receiver to pull `amount` of `asset_id` through
+    /// `pallet_assets::approve_transfer` beforehand.
+    #[ink(message)]
+    fn receive_position(&mut self,
+        asset_id: u128,
+        reference: u16,
+        account: AccountId,
+        amount: u128,
+        recipient: AccountId) -> Result<(), ContractError>;
+}
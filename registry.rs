@@ -0,0 +1,43 @@
+use ink::env::hash::Blake2x256;
+use ink::primitives::{AccountId, Hash};
+use scale::Encode;
+
+/// Registry error messages
+#[derive(scale::Encode, scale::Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum RegistryError {
+    /// Caller is not a contract
+    NotAContract,
+    /// Escrow is already registered
+    AlreadyRegistered,
+    /// Escrow is not registered
+    NotRegistered,
+}
+
+/// Shared interface of the companion registry escrow instances register with, so
+/// explorers can discover all deployed escrows without scanning the chain
+#[ink::trait_definition]
+pub trait EscrowRegistry {
+    /// Register the calling escrow contract, its code hash is read by the registry
+    #[ink(message)]
+    fn register(&mut self, asset_id: u128, owner: AccountId) -> Result<(), RegistryError>;
+
+    /// Update the liveness and status (0-Open, 1-Close) of the calling escrow
+    #[ink(message)]
+    fn heartbeat(&mut self, status: u8) -> Result<(), RegistryError>;
+}
+
+/// Address `pallet_contracts` assigns to a contract instantiated by `deployer` from
+/// `code_hash` with the SCALE encoded constructor call (selector and arguments) and
+/// `salt`, so integrators can precompute an escrow address, e.g. for an invoice,
+/// before instantiating it with that salt
+pub fn predict_address(deployer: AccountId,
+    code_hash: Hash,
+    constructor_args: &[u8],
+    salt: &[u8]) -> AccountId {
+
+    let entropy = (b"contract_addr_v1", deployer, code_hash, constructor_args, salt).encode();
+    let mut output = [0u8; 32];
+    ink::env::hash_bytes::<Blake2x256>(&entropy, &mut output);
+    AccountId::from(output)
+}
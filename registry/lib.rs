@@ -0,0 +1,164 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod escrow_registry {
+
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+
+    use escrow::registry::{self, EscrowRegistry, RegistryError};
+
+    /// Registered escrow instance
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct Entry {
+        /// Escrow contract
+        pub escrow: AccountId,
+        /// Code hash of the escrow contract when it registered or last reported
+        pub code_hash: Hash,
+        /// Escrow asset
+        pub asset_id: u128,
+        /// Escrow owner
+        pub owner: AccountId,
+        /// Status (0-Open, 1-Close)
+        pub status: u8,
+        /// Block number the escrow registered
+        pub registered_at: BlockNumber,
+        /// Block number of the last heartbeat
+        pub last_seen: BlockNumber,
+    }
+
+    /// Escrow registered
+    #[ink(event)]
+    pub struct Registered {
+        #[ink(topic)]
+        escrow: AccountId,
+        #[ink(topic)]
+        asset_id: u128,
+        owner: AccountId,
+        code_hash: Hash,
+    }
+
+    /// Registry storage
+    #[ink(storage)]
+    pub struct Registry {
+        /// Registered escrows
+        pub entries: Mapping<AccountId, Entry>,
+        /// Escrows by registration order, `0..count` enumerate the registry
+        pub escrows: Mapping<u32, AccountId>,
+        /// Number of registered escrows
+        pub count: u32,
+    }
+
+    impl Registry {
+
+        /// Create an empty registry
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                entries: Mapping::default(),
+                escrows: Mapping::default(),
+                count: 0,
+            }
+        }
+
+        /// Get a registered escrow
+        #[ink(message)]
+        pub fn get(&self, escrow: AccountId) -> Option<Entry> {
+            self.entries.get(escrow)
+        }
+
+        /// Get the number of registered escrows
+        #[ink(message)]
+        pub fn count(&self) -> u32 {
+            self.count
+        }
+
+        /// Get the registered escrows in registration order, paginated
+        #[ink(message)]
+        pub fn list(&self, offset: u32, limit: u32) -> Vec<Entry> {
+            (offset..self.count.min(offset.saturating_add(limit)))
+                .filter_map(|n| self.escrows.get(n))
+                .filter_map(|escrow| self.entries.get(escrow))
+                .collect()
+        }
+
+        /// Get the registered escrows of an asset, paginated over the registration
+        /// order
+        #[ink(message)]
+        pub fn list_by_asset(&self, asset_id: u128, offset: u32, limit: u32) -> Vec<Entry> {
+            self.list(offset, limit)
+                .into_iter()
+                .filter(|e| e.asset_id == asset_id)
+                .collect()
+        }
+
+        /// Get the address of an escrow `deployer` would instantiate from `code_hash`
+        /// with the SCALE encoded constructor call and `salt`, before it exists
+        #[ink(message)]
+        pub fn predict_address(&self,
+            deployer: AccountId,
+            code_hash: Hash,
+            salt: Vec<u8>,
+            constructor_args: Vec<u8>) -> AccountId {
+            registry::predict_address(deployer, code_hash, &constructor_args, &salt)
+        }
+    }
+
+    impl EscrowRegistry for Registry {
+
+        /// Register the calling escrow contract
+        #[ink(message)]
+        fn register(&mut self, asset_id: u128, owner: AccountId) -> Result<(), RegistryError> {
+            let caller = self.env().caller();
+
+            // Only contracts can register, their code hash is recorded
+            let code_hash = self.env()
+                .code_hash(&caller)
+                .map_err(|_| RegistryError::NotAContract)?;
+
+            if self.entries.contains(caller) {
+                return Err(RegistryError::AlreadyRegistered);
+            }
+
+            let block_number = self.env().block_number();
+            self.entries.insert(caller, &Entry {
+                escrow: caller,
+                code_hash,
+                asset_id,
+                owner,
+                status: 0,
+                registered_at: block_number,
+                last_seen: block_number,
+            });
+            self.escrows.insert(self.count, &caller);
+            self.count += 1;
+
+            self.env().emit_event(Registered {
+                escrow: caller,
+                asset_id,
+                owner,
+                code_hash,
+            });
+
+            Ok(())
+        }
+
+        /// Update the liveness and status of the calling escrow, an upgraded escrow
+        /// also reports its new code hash
+        #[ink(message)]
+        fn heartbeat(&mut self, status: u8) -> Result<(), RegistryError> {
+            let caller = self.env().caller();
+
+            let mut entry = self.entries.get(caller).ok_or(RegistryError::NotRegistered)?;
+            entry.status = status;
+            entry.last_seen = self.env().block_number();
+            if let Ok(code_hash) = self.env().code_hash(&caller) {
+                entry.code_hash = code_hash;
+            }
+            self.entries.insert(caller, &entry);
+
+            Ok(())
+        }
+    }
+}
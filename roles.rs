@@ -0,0 +1,17 @@
+/// Roles splitting the manager duties among a team, granted per account by the
+/// owner. The manager holds every role but `Arbiter`.
+#[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub enum Role {
+    /// Adds escrow accounts (`add`, `add_auto`)
+    Adder,
+    /// Overrides releases (`force_release`, `force_release_split`,
+    /// `force_release_partial`)
+    Releaser,
+    /// Opens and closes the escrow
+    Pauser,
+    /// Reads the internal notes, like the appointed auditor
+    Auditor,
+    /// Resolves disputes, the only role the manager does not hold
+    Arbiter,
+}
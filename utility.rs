@@ -0,0 +1,33 @@
+use ink::prelude::vec::Vec;
+use scale::{Compact, Encode, Output};
+
+use crate::assets::{PalletIndices, RuntimeCall};
+
+/// Defines relevant `Utility` pallet calls for settlement runs.
+#[ink::scale_derive(Encode)]
+pub enum UtilityCall {
+    /// Send a batch of dispatch calls and atomically execute them, the whole batch
+    /// reverts if any call fails.
+    ///
+    /// Used to dispatch the aggregated transfers of a settlement run at once.
+    #[codec(index = 2)]
+    BatchAll {
+        calls: Vec<RuntimeCall>,
+    },
+}
+
+impl UtilityCall {
+    /// Encode the call with the nested calls addressed by the pallet and call
+    /// indices of the runtime
+    pub fn encode_indexed<T: Output + ?Sized>(&self, indices: &PalletIndices, dest: &mut T) {
+        match self {
+            Self::BatchAll { calls } => {
+                dest.push_byte(indices.calls.utility_batch_all);
+                Compact(calls.len() as u32).encode_to(dest);
+                for call in calls {
+                    call.encode_indexed(indices, dest);
+                }
+            }
+        }
+    }
+}
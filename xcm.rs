@@ -0,0 +1,61 @@
+use scale::{Encode, Output};
+
+/// Defines relevant `PolkadotXcm` pallet calls for remote payouts.
+#[ink::scale_derive(Encode)]
+pub enum XcmCall {
+    /// Transfer some assets from the local chain to the destination chain through
+    /// their local, destination or remote reserve, or through teleports.
+    ///
+    /// Used to pay out recipients living on another chain.
+    #[codec(index = 11)]
+    TransferAssets {
+        dest: Encoded,
+        beneficiary: Encoded,
+        assets: Encoded,
+        fee_asset_item: u32,
+        weight_limit: WeightLimit,
+    },
+}
+
+/// Weight bought on the destination chain to execute the transfer.
+#[ink::scale_derive(Encode)]
+pub enum WeightLimit {
+    /// Buy as much weight as the fees allow.
+    #[codec(index = 0)]
+    Unlimited,
+}
+
+/// Value already SCALE-encoded by the client (e.g. a `VersionedLocation`), written
+/// as is without a length prefix.
+pub struct Encoded(pub ink::prelude::vec::Vec<u8>);
+
+impl Encode for Encoded {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0);
+    }
+}
+
+/// `Assets` pallet instance in the asset locations.
+const ASSETS_PALLET_INSTANCE: u8 = 50;
+
+/// Encode an amount of a local `Assets` pallet asset as XCM v4 `VersionedAssets`,
+/// located at `(PalletInstance(50), GeneralIndex(asset_id))`.
+pub fn local_assets(asset_id: u128, amount: u128) -> Encoded {
+    let mut assets = ink::prelude::vec![
+        4u8, // VersionedAssets::V4
+        4u8, // one asset (compact)
+        0u8, // parents
+        2u8, // Junctions::X2
+        4u8, // Junction::PalletInstance
+        ASSETS_PALLET_INSTANCE,
+        5u8, // Junction::GeneralIndex
+    ];
+    scale::Compact(asset_id).encode_to(&mut assets);
+    assets.push(0u8); // Fungibility::Fungible
+    scale::Compact(amount).encode_to(&mut assets);
+    Encoded(assets)
+}